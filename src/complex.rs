@@ -0,0 +1,240 @@
+use std::f64::consts::PI;
+
+/// A complex number, written `1J2` in APL.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+const I: Complex = Complex { re: 0., im: 1. };
+const ONE: Complex = Complex { re: 1., im: 0. };
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    pub fn real(re: f64) -> Complex {
+        Complex { re, im: 0. }
+    }
+
+    pub fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+
+    pub fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+
+    pub fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+
+    pub fn div(self, other: Complex) -> Complex {
+        let denominator = other.re * other.re + other.im * other.im;
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / denominator,
+            (self.im * other.re - self.re * other.im) / denominator,
+        )
+    }
+
+    pub fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+
+    pub fn conj(self) -> Complex {
+        Complex::new(self.re, -self.im)
+    }
+
+    pub fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// The angle of the number with the positive real axis.
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn exp(self) -> Complex {
+        let magnitude = self.re.exp();
+        Complex::new(magnitude * self.im.cos(), magnitude * self.im.sin())
+    }
+
+    /// The principal value of the natural logarithm.
+    pub fn ln(self) -> Complex {
+        Complex::new(self.abs().ln(), self.arg())
+    }
+
+    /// `self` raised to the power `other`. Whole powers are computed by
+    /// repeated multiplication, so that `0J1*2` is exactly `¯1`.
+    pub fn pow(self, other: Complex) -> Complex {
+        if other.im == 0. && other.re.fract() == 0. && other.re.abs() <= i32::MAX as f64 {
+            let mut exponent = other.re.abs() as u32;
+            let (mut base, mut result) = (self, ONE);
+            while exponent > 0 {
+                if exponent % 2 == 1 {
+                    result = result.mul(base);
+                }
+                base = base.mul(base);
+                exponent /= 2;
+            }
+            return if other.re < 0. { ONE.div(result) } else { result };
+        }
+        if other == Complex::real(0.5) {
+            return self.sqrt();
+        }
+        if self == Complex::real(0.) {
+            return match other.re > 0. {
+                true => Complex::real(0.),
+                false => Complex::real(f64::NAN),
+            };
+        }
+        other.mul(self.ln()).exp()
+    }
+
+    /// The principal square root, computed from the magnitude so that the
+    /// roots of negative numbers are exactly imaginary.
+    pub fn sqrt(self) -> Complex {
+        let magnitude = self.abs();
+        let im = ((magnitude - self.re) / 2.).sqrt();
+        Complex::new(((magnitude + self.re) / 2.).sqrt(), if self.im < 0. { -im } else { im })
+    }
+
+    pub fn sin(self) -> Complex {
+        Complex::new(self.re.sin() * self.im.cosh(), self.re.cos() * self.im.sinh())
+    }
+
+    pub fn cos(self) -> Complex {
+        Complex::new(self.re.cos() * self.im.cosh(), -self.re.sin() * self.im.sinh())
+    }
+
+    pub fn tan(self) -> Complex {
+        self.sin().div(self.cos())
+    }
+
+    pub fn sinh(self) -> Complex {
+        Complex::new(self.re.sinh() * self.im.cos(), self.re.cosh() * self.im.sin())
+    }
+
+    pub fn cosh(self) -> Complex {
+        Complex::new(self.re.cosh() * self.im.cos(), self.re.sinh() * self.im.sin())
+    }
+
+    pub fn tanh(self) -> Complex {
+        self.sinh().div(self.cosh())
+    }
+
+    pub fn asin(self) -> Complex {
+        // -i ln(iz + √(1-z²))
+        let root = ONE.sub(self.mul(self)).sqrt();
+        I.mul(self).add(root).ln().mul(I).neg()
+    }
+
+    pub fn acos(self) -> Complex {
+        Complex::real(PI / 2.).sub(self.asin())
+    }
+
+    pub fn atan(self) -> Complex {
+        // i/2 (ln(1-iz) - ln(1+iz))
+        let iz = I.mul(self);
+        let difference = ONE.sub(iz).ln().sub(ONE.add(iz).ln());
+        I.mul(difference).mul(Complex::real(0.5))
+    }
+
+    pub fn asinh(self) -> Complex {
+        self.add(self.mul(self).add(ONE).sqrt()).ln()
+    }
+
+    pub fn acosh(self) -> Complex {
+        let root = self.add(ONE).sqrt().mul(self.sub(ONE).sqrt());
+        self.add(root).ln()
+    }
+
+    pub fn atanh(self) -> Complex {
+        ONE.add(self).div(ONE.sub(self)).ln().mul(Complex::real(0.5))
+    }
+
+    /// The circular function numbered `code`, as `code○self`.
+    pub fn circular(self, code: Complex) -> Complex {
+        let z = self;
+        if code.im != 0. || code.re.fract() != 0. {
+            return Complex::real(f64::NAN);
+        }
+        match code.re as i32 {
+            0 => ONE.sub(z.mul(z)).sqrt(),
+            1 => z.sin(),
+            2 => z.cos(),
+            3 => z.tan(),
+            4 => ONE.add(z.mul(z)).sqrt(),
+            5 => z.sinh(),
+            6 => z.cosh(),
+            7 => z.tanh(),
+            8 => ONE.add(z.mul(z)).neg().sqrt(),
+            9 => Complex::real(z.re),
+            10 => Complex::real(z.abs()),
+            11 => Complex::real(z.im),
+            12 => Complex::real(z.arg()),
+            -1 => z.asin(),
+            -2 => z.acos(),
+            -3 => z.atan(),
+            -4 if z == ONE.neg() => Complex::real(0.),
+            -4 => z.add(ONE).mul(z.sub(ONE).div(z.add(ONE)).sqrt()),
+            -5 => z.asinh(),
+            -6 => z.acosh(),
+            -7 => z.atanh(),
+            -8 => ONE.add(z.mul(z)).neg().sqrt().neg(),
+            -9 => z,
+            -10 => z.conj(),
+            -11 => I.mul(z),
+            -12 => I.mul(z).exp(),
+            _ => Complex::real(f64::NAN),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use crate::repl::evaluate;
+
+    #[test]
+    fn complex_literals() {
+        assert_eq!(evaluate("1J2 1j2"), Ok("1J2 1J2".to_string()));
+        assert_eq!(evaluate("0J¯1"), Ok("0J¯1".to_string()));
+        assert_eq!(evaluate("¯1.5J2.5"), Ok("¯1.5J2.5".to_string()));
+        assert_eq!(evaluate("1E2J1"), Ok("100J1".to_string()));
+        assert_eq!(evaluate("1J0"), Ok("1".to_string()));
+        assert_eq!(evaluate("1J").unwrap_err().kind, ErrorKind::Syntax);
+    }
+
+    #[test]
+    fn complex_arithmetic() {
+        assert_eq!(evaluate("1J2+3J4"), Ok("4J6".to_string()));
+        assert_eq!(evaluate("1J2×3J4"), Ok("¯5J10".to_string()));
+        assert_eq!(evaluate("1J2÷3J4"), Ok("0.44J0.08".to_string()));
+        assert_eq!(evaluate("1J2-1J2"), Ok("0".to_string()));
+        assert_eq!(evaluate("1J2=1J2"), Ok("1".to_string()));
+        assert_eq!(evaluate("1J2<2").unwrap_err().kind, ErrorKind::Domain);
+    }
+
+    #[test]
+    fn complex_monadic_functions() {
+        assert_eq!(evaluate("+1J2"), Ok("1J¯2".to_string()));
+        assert_eq!(evaluate("|3J4"), Ok("5".to_string()));
+        assert_eq!(evaluate("×3J4"), Ok("0.6J0.8".to_string()));
+        assert_eq!(evaluate("⍟¯1"), Ok("0J3.141592654".to_string()));
+    }
+
+    #[test]
+    fn complex_powers() {
+        assert_eq!(evaluate("¯1*0.5"), Ok("0J1".to_string()));
+        assert_eq!(evaluate("0J1*2"), Ok("¯1".to_string()));
+        assert_eq!(evaluate("2*0J1"), Ok("0.7692389014J0.6389612763".to_string()));
+        // The fourth roots of unity
+        assert_eq!(evaluate("1E¯10>|(*0J1×○0.5×⍳4)-0J1 ¯1 0J¯1 1"), Ok("1 1 1 1".to_string()));
+    }
+}
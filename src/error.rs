@@ -0,0 +1,191 @@
+use crate::token::Span;
+use std::fmt;
+
+/// The classes of errors APL reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    /// An argument is outside the domain of a function.
+    Domain,
+    /// Arguments have incompatible lengths.
+    Length,
+    /// Arguments have incompatible ranks.
+    Rank,
+    /// An axis is out of range, or given to a function that takes none.
+    Axis,
+    /// An index is out of range.
+    #[allow(dead_code)]
+    Index,
+    /// A name has no value.
+    Value,
+    /// The source is not valid APL.
+    Syntax,
+    /// Valid APL that is not implemented.
+    Nonce,
+    /// A limit of the interpreter, such as the recursion depth, is exceeded.
+    Limit,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ErrorKind::Domain => "DOMAIN",
+            ErrorKind::Length => "LENGTH",
+            ErrorKind::Rank => "RANK",
+            ErrorKind::Axis => "AXIS",
+            ErrorKind::Index => "INDEX",
+            ErrorKind::Value => "VALUE",
+            ErrorKind::Syntax => "SYNTAX",
+            ErrorKind::Nonce => "NONCE",
+            ErrorKind::Limit => "LIMIT",
+        };
+        write!(f, "{} ERROR", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Where in the source the error occurred, when it is known.
+    pub span: Option<Span>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: &str) -> Error {
+        Error {
+            kind,
+            message: message.to_string(),
+            span: None,
+        }
+    }
+
+    pub fn domain(message: &str) -> Error {
+        Error::new(ErrorKind::Domain, message)
+    }
+
+    pub fn length(message: &str) -> Error {
+        Error::new(ErrorKind::Length, message)
+    }
+
+    pub fn rank(message: &str) -> Error {
+        Error::new(ErrorKind::Rank, message)
+    }
+
+    pub fn axis(message: &str) -> Error {
+        Error::new(ErrorKind::Axis, message)
+    }
+
+    #[allow(dead_code)]
+    pub fn index(message: &str) -> Error {
+        Error::new(ErrorKind::Index, message)
+    }
+
+    pub fn value(message: &str) -> Error {
+        Error::new(ErrorKind::Value, message)
+    }
+
+    pub fn syntax(message: &str) -> Error {
+        Error::new(ErrorKind::Syntax, message)
+    }
+
+    pub fn nonce(message: &str) -> Error {
+        Error::new(ErrorKind::Nonce, message)
+    }
+
+    pub fn limit(message: &str) -> Error {
+        Error::new(ErrorKind::Limit, message)
+    }
+
+    /// Locate the error at `span`, unless it is already located.
+    pub fn at(mut self, span: Span) -> Error {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    /// Forget where the error occurred, for errors raised in a function
+    /// defined elsewhere which are reported where the function is called.
+    pub fn without_span(mut self) -> Error {
+        self.span = None;
+        self
+    }
+
+    /// The line and column, both counted from 1, where the error occurred
+    /// in `source`.
+    pub fn location(&self, source: &str) -> Option<(usize, usize)> {
+        let before = source.get(..self.span?.start)?;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Some((
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        ))
+    }
+
+    /// Describe the error followed by the line of `source` where it occurred,
+    /// with carets under the part of the line at fault.
+    pub fn report(&self, source: &str) -> String {
+        let (span, (_, column)) = match (self.span, self.location(source)) {
+            (Some(span), Some(location)) => (span, location),
+            _ => return self.to_string(),
+        };
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[span.start..].find('\n').map_or(source.len(), |i| span.start + i);
+        let width = source
+            .get(span.start..span.end.min(line_end))
+            .map_or(0, |part| part.chars().count());
+        format!(
+            "{}\n      {}\n      {}{}",
+            self,
+            &source[line_start..line_end],
+            " ".repeat(column - 1),
+            "^".repeat(width.max(1))
+        )
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message.is_empty() {
+            true => write!(f, "{}", self.kind),
+            false => write!(f, "{}: {}", self.kind, self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repl::evaluate;
+
+    fn report(source: &str) -> String {
+        evaluate(source).unwrap_err().report(source)
+    }
+
+    #[test]
+    fn carets_point_at_the_failing_primitive() {
+        assert_eq!(report("1 2+1 2 3"), "LENGTH ERROR\n      1 2+1 2 3\n         ^");
+        assert_eq!(report("1+$"), "SYNTAX ERROR: unexpected character $\n      1+$\n        ^");
+        assert_eq!(report("1 2,[2.5]3 4"), "AXIS ERROR\n      1 2,[2.5]3 4\n         ^^^^^^");
+    }
+
+    #[test]
+    fn carets_count_characters_not_bytes() {
+        assert_eq!(report("⍳⍳'a'"), "DOMAIN ERROR\n      ⍳⍳'a'\n       ^");
+        // Errors in a dfn are reported where it is called
+        assert_eq!(report("f←{⍵+'a'} ⋄ f 1"), "DOMAIN ERROR\n      f←{⍵+'a'} ⋄ f 1\n                  ^");
+    }
+
+    #[test]
+    fn carets_are_on_the_line_of_the_error() {
+        // Spans are in bytes, the `+` of the second line starting at 11
+        let error = Error::domain("").at(Span { start: 11, end: 12 });
+        assert_eq!(error.location("a←1\nb←a+'x'"), Some((2, 4)));
+        assert_eq!(error.report("a←1\nb←a+'x'"), "DOMAIN ERROR\n      b←a+'x'\n         ^");
+    }
+
+    #[test]
+    fn errors_without_a_location_have_no_caret() {
+        assert_eq!(Error::length("").report("1 2+1 2 3"), "LENGTH ERROR");
+        let error = Error::domain("").at(Span { start: 40, end: 41 });
+        assert_eq!(error.report("1+1"), "DOMAIN ERROR");
+    }
+}
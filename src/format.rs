@@ -0,0 +1,252 @@
+use crate::number::Number;
+use crate::value::Value;
+
+/// Number of significant digits used to display a number that is not whole.
+const PRINT_PRECISION: usize = 10;
+
+/// Render a value the way APL displays it.
+///
+/// Simple arrays are laid out in columns aligned to the right, with the
+/// planes of arrays of rank 3 or more separated by blank lines. Nested arrays
+/// are drawn as a grid of boxes, one per item.
+pub fn format(value: &Value) -> String {
+    lines(value).join("\n")
+}
+
+pub fn format_number(n: Number) -> String {
+    let magnitude = match n {
+        Number::Complex(z) => {
+            let (re, im) = (Number::Float(z.re), Number::Float(z.im));
+            return format!("{}J{}", format_number(re), format_number(im));
+        }
+        Number::Float(x) if x.is_infinite() => "∞".to_string(),
+        Number::Float(x) => {
+            let scientific = format!("{:.*e}", PRINT_PRECISION - 1, x.abs());
+            let (mantissa, exponent) = scientific.split_once('e').unwrap();
+            let exponent: i32 = exponent.parse().unwrap();
+            if exponent < -6 || exponent >= PRINT_PRECISION as i32 {
+                let mantissa = trim_zeros(mantissa);
+                format!("{}E{}", mantissa, exponent.to_string().replace('-', "¯"))
+            } else {
+                let decimals = (PRINT_PRECISION as i32 - 1 - exponent).max(0) as usize;
+                trim_zeros(&format!("{:.*}", decimals, x.abs())).to_string()
+            }
+        }
+        _ => n.as_int().unwrap().unsigned_abs().to_string(),
+    };
+    if n.as_f64() < 0. {
+        format!("¯{}", magnitude)
+    } else {
+        magnitude
+    }
+}
+
+fn trim_zeros(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
+
+fn lines(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array { shape, values, .. } => {
+            if values.iter().all(Value::is_simple_scalar) {
+                simple_lines(shape, values)
+            } else {
+                boxed_lines(shape, values)
+            }
+        }
+        _ => vec![scalar(value)],
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Number(n) => format_number(*n),
+        Value::Char(c) => c.to_string(),
+        Value::None => String::new(),
+        Value::MonadicFunction(f) => f.node.to_string(),
+        Value::DyadicFunction(f) => f.node.to_string(),
+        Value::Dfn(f) => f.node.to_string(),
+        Value::DerivedFunction(f) => f.node.to_string(),
+        Value::Array { .. } => format(value),
+    }
+}
+
+fn width(line: &str) -> usize {
+    line.chars().count()
+}
+
+// Split a shape into the number of rows and columns it is laid out on.
+fn rows_and_columns(shape: &[usize]) -> (usize, usize) {
+    match shape.split_last() {
+        Some((&columns, rest)) => (rest.iter().product(), columns),
+        None => (1, 1),
+    }
+}
+
+// How many blank lines separate row `row` from the previous one: one for
+// each axis above the last two that starts over at this row.
+fn blank_lines_before(shape: &[usize], row: usize) -> usize {
+    if row == 0 || shape.len() < 3 {
+        return 0;
+    }
+    let mut blank_lines = 0;
+    let mut stride = 1;
+    for axis in shape[..shape.len() - 1].iter().skip(1).rev() {
+        stride *= axis;
+        if row.is_multiple_of(stride) {
+            blank_lines += 1;
+        }
+    }
+    blank_lines
+}
+
+fn simple_lines(shape: &[usize], values: &[Value]) -> Vec<String> {
+    let (rows, columns) = rows_and_columns(shape);
+    if values.is_empty() {
+        return vec![String::new(); if columns == 0 { rows } else { 0 }];
+    }
+    let cells: Vec<String> = values.iter().map(scalar).collect();
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            (0..rows)
+                .map(|row| width(&cells[row * columns + column]))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    // Characters are written next to each other, anything else is spaced
+    let textual: Vec<bool> = (0..columns)
+        .map(|column| (0..rows).all(|row| matches!(values[row * columns + column], Value::Char(_))))
+        .collect();
+    let mut lines: Vec<String> = Vec::new();
+    for row in 0..rows {
+        for _ in 0..blank_lines_before(shape, row) {
+            lines.push(String::new());
+        }
+        let mut line = String::new();
+        for column in 0..columns {
+            if column > 0 && !(textual[column - 1] && textual[column]) {
+                line.push(' ');
+            }
+            let cell = &cells[row * columns + column];
+            line.push_str(&" ".repeat(widths[column] - width(cell)));
+            line.push_str(cell);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn boxed_lines(shape: &[usize], values: &[Value]) -> Vec<String> {
+    let (rows, columns) = rows_and_columns(shape);
+    let blocks: Vec<Vec<String>> = values.iter().map(lines).collect();
+    let plane = match shape.len() {
+        0 | 1 => rows,
+        n => shape[n - 2],
+    };
+    let mut result: Vec<String> = Vec::new();
+    let mut first_row = 0;
+    while first_row < rows {
+        for _ in 0..blank_lines_before(shape, first_row) {
+            result.push(String::new());
+        }
+        let grid_rows = first_row..first_row + plane;
+        let block = |row: usize, column: usize| &blocks[row * columns + column];
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                grid_rows
+                    .clone()
+                    .flat_map(|row| block(row, column).iter().map(|line| width(line)))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let border = |left: char, middle: char, right: char| {
+            let segments: Vec<String> = widths.iter().map(|&w| "─".repeat(w)).collect();
+            format!("{}{}{}", left, segments.join(&middle.to_string()), right)
+        };
+        result.push(border('┌', '┬', '┐'));
+        for row in grid_rows.clone() {
+            if row > first_row {
+                result.push(border('├', '┼', '┤'));
+            }
+            let height = (0..columns).map(|column| block(row, column).len()).max().unwrap_or(0);
+            for line_number in 0..height {
+                let mut line = String::from("│");
+                for (column, &w) in widths.iter().enumerate() {
+                    let cell = block(row, column).get(line_number).map_or("", |line| line.as_str());
+                    line.push_str(cell);
+                    line.push_str(&" ".repeat(w - width(cell)));
+                    line.push('│');
+                }
+                result.push(line);
+            }
+        }
+        result.push(border('└', '┴', '┘'));
+        first_row += plane.max(1);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::Interpreter;
+    use crate::node::Node;
+    use crate::{lexer, parser};
+
+    // Evaluate the single expression `source` and format its value.
+    fn display(source: &str) -> String {
+        let mut tokens = lexer::scan_tokens(source).unwrap();
+        tokens.reverse();
+        let mut interpreter = Interpreter::new();
+        let ast = parser::parse(&tokens, &interpreter.function_names()).unwrap();
+        let Node::Statement { children, .. } = ast else {
+            panic!("expected a statement");
+        };
+        format(&interpreter.interpret(&children[0]).unwrap())
+    }
+
+    #[test]
+    fn negative_numbers_use_high_minus() {
+        assert_eq!(display("¯1 2 ¯3.5"), "¯1 2 ¯3.5");
+        assert_eq!(display("¯1E¯20"), "¯1E¯20");
+    }
+
+    #[test]
+    fn matrix_columns_are_right_aligned() {
+        assert_eq!(display("2 3⍴1 ¯20 300"), "1 ¯20 300\n1 ¯20 300");
+        assert_eq!(display("3 2⍴1 10 100"), "  1  10\n100   1\n 10 100");
+    }
+
+    #[test]
+    fn planes_are_separated_by_blank_lines() {
+        assert_eq!(display("2 2 3⍴⍳12"), " 1  2  3\n 4  5  6\n\n 7  8  9\n10 11 12");
+        assert_eq!(display("2 2 1 1⍴⍳4"), "1\n\n2\n\n\n3\n\n4");
+    }
+
+    #[test]
+    fn nested_arrays_are_boxed() {
+        assert_eq!(
+            display("1 (2 3) (2 2⍴⍳4)"),
+            "┌─┬───┬───┐\n│1│2 3│1 2│\n│ │   │3 4│\n└─┴───┴───┘"
+        );
+        assert_eq!(
+            display("(1 (2 3)) 'ab'"),
+            "┌───────┬──┐\n│┌─┬───┐│ab│\n││1│2 3││  │\n│└─┴───┘│  │\n└───────┴──┘"
+        );
+    }
+
+    #[test]
+    fn functions_show_their_source() {
+        assert_eq!(display("+"), "+");
+        assert_eq!(display("{⍵ ⍵}"), "{⍵ ⍵}");
+        assert_eq!(display("∘.×"), "∘.×");
+        assert_eq!(display(",[0.5]"), ",[0.5]");
+        assert_eq!(display("{⍺←0 ⋄ ⍵=0:⍺ ⋄ (⍺+1) ∇ ⍵-1}¨"), "{⍺←0 ⋄ ⍵=0:⍺ ⋄ (⍺+1)∇ ⍵-1}¨");
+    }
+}
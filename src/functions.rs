@@ -1,169 +1,1265 @@
+use crate::error::Error;
+use crate::complex::Complex;
+use crate::number::{Number, COMPARISON_TOLERANCE};
 use crate::value::Value;
 
-pub fn conjugate(omega: &Value) -> Result<Value, String> {
-    todo!()
+// Apply the scalar function `f` to every simple scalar of `omega`, at any
+// depth, keeping the structure of `omega`.
+fn pervade_monad<F>(omega: &Value, f: &F) -> Result<Value, Error>
+where
+    F: Fn(&Value) -> Result<Value, Error>,
+{
+    match omega {
+        Value::Array { shape, values, .. } => {
+            let values = values
+                .iter()
+                .map(|value| pervade_monad(value, f))
+                .collect::<Result<Vec<Value>, Error>>()?;
+            Ok(Value::new_array(shape.clone(), values))
+        }
+        _ => f(omega),
+    }
 }
 
-pub fn plus(alpha: &Value, omega: &Value) -> Result<Value, String> {
-    match (alpha, omega) {
-        (Value::Number(value1), Value::Number(value2)) => {
-            return Ok(Value::Number(value1 + value2));
-        }
-        (Value::Array(values1), Value::Array(values2)) => {
-            if values1.len() != values2.len() {
-                return Err("Vectors don't have the same rank".to_string());
-            }
-            let mut vector: Vec<Value> = Vec::new();
-            for i in 0..values1.len() {
-                if let (Value::Number(v1), Value::Number(v2)) = (&values1[i], &values2[i]) {
-                    vector.push(Value::Number(v1 + v2))
-                }
+/// The shape of the result of pairing the items of `alpha` and `omega`.
+///
+/// Both arguments must have the same shape, unless one of them has a single
+/// item which is then paired with every item of the other.
+pub fn paired_shape(alpha: &Value, omega: &Value) -> Result<Vec<usize>, Error> {
+    let (shape1, shape2) = (alpha.shape(), omega.shape());
+    let (length1, length2) = (shape1.iter().product::<usize>(), shape2.iter().product::<usize>());
+    if shape1 == shape2 {
+        Ok(shape1)
+    } else if length1 == 1 && (length2 != 1 || shape2.len() >= shape1.len()) {
+        Ok(shape2)
+    } else if length2 == 1 {
+        Ok(shape1)
+    } else if shape1.len() != shape2.len() {
+        Err(Error::rank(""))
+    } else {
+        Err(Error::length(""))
+    }
+}
+
+// Apply the scalar function `f` to the simple scalars of `alpha` and `omega`
+// pairwise, at any depth, pairing items at each level as `paired_shape` does.
+fn pervade_dyad<F>(alpha: &Value, omega: &Value, f: &F) -> Result<Value, Error>
+where
+    F: Fn(&Value, &Value) -> Result<Value, Error>,
+{
+    if alpha.is_simple_scalar() && omega.is_simple_scalar() {
+        return f(alpha, omega);
+    }
+    let shape = paired_shape(alpha, omega)?;
+    let (values1, values2) = (alpha.ravel(), omega.ravel());
+    let values = (0..shape.iter().product())
+        .map(|i| pervade_dyad(&values1[i % values1.len()], &values2[i % values2.len()], f))
+        .collect::<Result<Vec<Value>, Error>>()?;
+    Ok(Value::new_array(shape, values))
+}
+
+// A number computed by a scalar function, which is outside of its domain
+// when it is not finite, such as a division by zero or the square root of a
+// negative number.
+fn real(n: Number) -> Result<Value, Error> {
+    match n.is_finite() {
+        true => Ok(Value::Number(n)),
+        false => Err(Error::domain("")),
+    }
+}
+
+// Apply `f` to every real number of `omega`.
+fn monadic_scalar(omega: &Value, f: fn(Number) -> Number) -> Result<Value, Error> {
+    pervade_monad(omega, &|value| match value {
+        Value::Number(n) if !n.is_complex() => real(f(*n)),
+        _ => Err(Error::domain("")),
+    })
+}
+
+// Apply `f` to the real numbers of `alpha` and `omega` pairwise.
+fn dyadic_scalar(alpha: &Value, omega: &Value, f: fn(Number, Number) -> Number) -> Result<Value, Error> {
+    pervade_dyad(alpha, omega, &|a, b| match (a, b) {
+        (Value::Number(a), Value::Number(b)) if !a.is_complex() && !b.is_complex() => real(f(*a, *b)),
+        _ => Err(Error::domain("")),
+    })
+}
+
+// Apply `f` to every number of `omega`, or `g` when the number is complex or
+// when its result for a real number is not real.
+fn monadic_complex(
+    omega: &Value,
+    f: fn(Number) -> Number,
+    g: fn(Complex) -> Complex,
+) -> Result<Value, Error> {
+    pervade_monad(omega, &|value| match value {
+        Value::Number(n) => {
+            let result = match n.is_complex() {
+                true => Number::complex(g(n.as_complex())),
+                false => f(*n),
+            };
+            match result.is_nan() {
+                true => real(Number::complex(g(n.as_complex()))),
+                false => real(result),
             }
-            return Ok(Value::Array(vector));
-        }
-        (Value::Array(values), Value::Number(value))
-        | (Value::Number(value), Value::Array(values)) => {
-            let mut vector: Vec<Value> = Vec::new();
-            for v in values {
-                if let Value::Number(v1) = &v {
-                    vector.push(Value::Number(v1 + value))
-                }
+        }
+        _ => Err(Error::domain("")),
+    })
+}
+
+// Apply `f` to the numbers of `alpha` and `omega` pairwise, or `g` when
+// either is complex or when the result for real numbers is not real.
+fn dyadic_complex(
+    alpha: &Value,
+    omega: &Value,
+    f: fn(Number, Number) -> Number,
+    g: fn(Complex, Complex) -> Complex,
+) -> Result<Value, Error> {
+    pervade_dyad(alpha, omega, &|a, b| match (a, b) {
+        (Value::Number(a), Value::Number(b)) => {
+            let complex = || Number::complex(g(a.as_complex(), b.as_complex()));
+            let result = match a.is_complex() || b.is_complex() {
+                true => complex(),
+                false => f(*a, *b),
+            };
+            match result.is_nan() {
+                true => real(complex()),
+                false => real(result),
             }
-            return Ok(Value::Array(vector));
         }
-        _ => unreachable!(),
+        _ => Err(Error::domain("")),
+    })
+}
+
+// Apply `f` to the float value of a number.
+fn inexact(n: Number, f: fn(f64) -> f64) -> Number {
+    Number::float(f(n.as_f64()))
+}
+
+// The largest whole number not greater than `x`, taking `x` as whole when it
+// is tolerantly equal to a whole number.
+fn tolerant_floor(x: f64) -> f64 {
+    let nearest = x.round();
+    match Number::float(x).tolerantly_equals(Number::float(nearest)) {
+        true => nearest,
+        false => x.floor(),
     }
 }
 
-pub fn direction(omega: &Value) -> Result<Value, String> {
-    match omega {
-        Value::Number(value) => {
-            return Ok(Value::Number(value / value.abs()));
-        }
-        Value::Array(values) => {
-            let mut vector: Vec<Value> = Vec::new();
-            for value in values {
-                if let Value::Number(n) = value {
-                    vector.push(Value::Number(n / n.abs()))
-                }
-            }
-            return Ok(Value::Array(vector));
+pub fn conjugate(omega: &Value) -> Result<Value, Error> {
+    monadic_complex(omega, |n| n, Complex::conj)
+}
+
+pub fn plus(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_complex(alpha, omega, |a, b| a.exact_or(b, i64::checked_add, |a, b| a + b), Complex::add)
+}
+
+pub fn direction(omega: &Value) -> Result<Value, Error> {
+    let f = |n: Number| match n.as_int() {
+        Some(n) => Number::int(n.signum()),
+        None => inexact(n, |x| if x == 0. { 0. } else { x.signum() }),
+    };
+    monadic_complex(omega, f, |z| z.div(Complex::real(z.abs())))
+}
+
+pub fn times(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_complex(alpha, omega, |a, b| a.exact_or(b, i64::checked_mul, |a, b| a * b), Complex::mul)
+}
+
+pub fn ceiling(omega: &Value) -> Result<Value, Error> {
+    monadic_scalar(omega, |n| match n {
+        Number::Float(x) => Number::float(-tolerant_floor(-x)),
+        _ => n,
+    })
+}
+
+pub fn maximum(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, |a, b| if a.is_less_than(b) { b } else { a })
+}
+
+pub fn floor(omega: &Value) -> Result<Value, Error> {
+    monadic_scalar(omega, |n| match n {
+        Number::Float(x) => Number::float(tolerant_floor(x)),
+        _ => n,
+    })
+}
+
+pub fn minimum(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, |a, b| if b.is_less_than(a) { b } else { a })
+}
+
+pub fn negate(omega: &Value) -> Result<Value, Error> {
+    monadic_complex(omega, |n| n.exact_or(n, |a, _| a.checked_neg(), |a, _| -a), Complex::neg)
+}
+
+pub fn minus(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_complex(alpha, omega, |a, b| a.exact_or(b, i64::checked_sub, |a, b| a - b), Complex::sub)
+}
+
+pub fn reciprocal(omega: &Value) -> Result<Value, Error> {
+    monadic_complex(omega, |n| inexact(n, |x| 1. / x), |z| Complex::real(1.).div(z))
+}
+
+pub fn divide(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    let f = |a: Number, b: Number| {
+        a.exact_or(
+            b,
+            |a, b| match a.checked_rem(b) {
+                Some(0) => a.checked_div(b),
+                _ => None,
+            },
+            |a, b| if a == 0. && b == 0. { 1. } else { a / b },
+        )
+    };
+    dyadic_complex(alpha, omega, f, Complex::div)
+}
+
+pub fn magnitude(omega: &Value) -> Result<Value, Error> {
+    monadic_complex(omega, |n| n.exact_or(n, |a, _| a.checked_abs(), |a, _| a.abs()), |z| Complex::real(z.abs()))
+}
+
+// The remainder of dividing `omega` by `alpha`, which has the sign of `alpha`.
+pub fn residue(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, |a, b| {
+        a.exact_or(
+            b,
+            |a, b| match a {
+                0 => Some(b),
+                _ => b.checked_rem_euclid(a.checked_abs()?).map(|r| if a < 0 && r != 0 { r + a } else { r }),
+            },
+            |a, b| if a == 0. { b } else { b - a * tolerant_floor(b / a) },
+        )
+    })
+}
+
+pub fn exponential(omega: &Value) -> Result<Value, Error> {
+    monadic_complex(omega, |n| inexact(n, f64::exp), Complex::exp)
+}
+
+pub fn power(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    let f = |a: Number, b: Number| a.exact_or(b, |a, b| a.checked_pow(u32::try_from(b).ok()?), f64::powf);
+    dyadic_complex(alpha, omega, f, Complex::pow)
+}
+
+pub fn natural_logarithm(omega: &Value) -> Result<Value, Error> {
+    monadic_complex(omega, |n| inexact(n, f64::ln), Complex::ln)
+}
+
+pub fn logarithm(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    let f = |a: Number, b: Number| match a == b {
+        true => Number::int(1),
+        false => Number::float(b.as_f64().ln() / a.as_f64().ln()),
+    };
+    dyadic_complex(alpha, omega, f, |a, b| match a == b {
+        true => Complex::real(1.),
+        false => b.ln().div(a.ln()),
+    })
+}
+
+// The gamma function, with the Lanczos approximation.
+fn gamma(x: f64) -> f64 {
+    const G: f64 = 7.;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection formula
+        let pi = std::f64::consts::PI;
+        return pi / ((pi * x).sin() * gamma(1. - x));
+    }
+    let x = x - 1.;
+    let t = x + G + 0.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.));
+    (2. * std::f64::consts::PI).sqrt() * t.powf(x + 0.5) * (-t).exp() * sum
+}
+
+// The factorial of `n`, exact for integers and extended to other numbers by
+// the gamma function. It is not defined for negative integers.
+fn factorial_of(n: Number) -> Number {
+    let exact = |n: i64, _| match n {
+        0.. => (1..=n).try_fold(1i64, i64::checked_mul),
+        _ => None,
+    };
+    let inexact = |x: f64, _| match x < 0. && x.fract() == 0. {
+        true => f64::NAN,
+        false => gamma(x + 1.),
+    };
+    n.exact_or(n, exact, inexact)
+}
+
+// The number of ways to choose `k` items among `n`, extended to negative
+// integers and to other numbers through the factorial.
+fn binomial_of(k: Number, n: Number) -> Number {
+    let (Some(k), Some(n)) = (k.as_int(), n.as_int()) else {
+        let (k, n) = (k.as_f64(), n.as_f64());
+        return Number::float(gamma(n + 1.) / (gamma(k + 1.) * gamma(n - k + 1.)));
+    };
+    // Choose among non-negative integers, multiplying by increasing ratios
+    // which keeps every partial product whole
+    let choose = |n: i64, k: i64| {
+        (0..k.min(n - k)).try_fold(1i64, |c, i| Some(c.checked_mul(n - i)? / (i + 1)))
+    };
+    let sign = |p: i64| if p % 2 == 0 { 1 } else { -1 };
+    let exact = if 0 <= k && k <= n {
+        choose(n, k)
+    } else if n < 0 && k >= 0 {
+        choose(k - n - 1, k).map(|c| sign(k) * c)
+    } else if n < 0 && k <= n {
+        choose(-k - 1, n - k).map(|c| sign(n - k) * c)
+    } else {
+        Some(0)
+    };
+    match exact {
+        Some(c) => Number::int(c),
+        None => {
+            let (k, n) = (k as f64, n as f64);
+            Number::float((gamma(n + 1.) / (gamma(k + 1.) * gamma(n - k + 1.))).round())
         }
-        _ => {
-            return Err("Domain Error".to_string());
+    }
+}
+
+pub fn factorial(omega: &Value) -> Result<Value, Error> {
+    monadic_scalar(omega, factorial_of)
+}
+
+pub fn binomial(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, binomial_of)
+}
+
+pub fn pi_times(omega: &Value) -> Result<Value, Error> {
+    monadic_complex(omega, |n| inexact(n, |x| std::f64::consts::PI * x), |z| Complex::real(std::f64::consts::PI).mul(z))
+}
+
+// The circular function numbered `code` applied to `n`. Codes whose results
+// are complex for real arguments give NaN.
+fn circular_of(code: Number, n: Number) -> Number {
+    let n = n.as_f64();
+    let result = match code.as_int() {
+        None => f64::NAN,
+        Some(0) => (1. - n * n).sqrt(),
+        Some(1) => n.sin(),
+        Some(2) => n.cos(),
+        Some(3) => n.tan(),
+        Some(4) => (1. + n * n).sqrt(),
+        Some(5) => n.sinh(),
+        Some(6) => n.cosh(),
+        Some(7) => n.tanh(),
+        Some(8) => (-1. - n * n).sqrt(),
+        Some(9 | -9 | -10) => n,
+        Some(10) => n.abs(),
+        Some(11) => 0.,
+        Some(12) => if n < 0. { std::f64::consts::PI } else { 0. },
+        Some(-1) => n.asin(),
+        Some(-2) => n.acos(),
+        Some(-3) => n.atan(),
+        Some(-4) => if n == -1. { 0. } else { (n + 1.) * ((n - 1.) / (n + 1.)).sqrt() },
+        Some(-5) => n.asinh(),
+        Some(-6) => n.acosh(),
+        Some(-7) => n.atanh(),
+        Some(-8) => -(-1. - n * n).sqrt(),
+        Some(_) => f64::NAN,
+    };
+    Number::float(result)
+}
+
+pub fn circular(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_complex(alpha, omega, circular_of, |code, z| z.circular(code))
+}
+
+// 1 if `condition` holds, 0 otherwise.
+fn boolean(condition: bool) -> Number {
+    Number::Bool(condition)
+}
+
+// Compare two scalars of any kind. Numbers are compared within the
+// comparison tolerance, and are never equal to characters.
+fn scalars_equal(a: &Value, b: &Value) -> Result<bool, Error> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => Ok(a.tolerantly_equals(*b)),
+        (Value::Char(a), Value::Char(b)) => Ok(a == b),
+        (Value::Number(_) | Value::Char(_), Value::Number(_) | Value::Char(_)) => Ok(false),
+        _ => Err(Error::domain("")),
+    }
+}
+
+pub fn equal(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    pervade_dyad(alpha, omega, &|a, b| Ok(Value::Number(boolean(scalars_equal(a, b)?))))
+}
+
+pub fn not_equal(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    pervade_dyad(alpha, omega, &|a, b| Ok(Value::Number(boolean(!scalars_equal(a, b)?))))
+}
+
+pub fn less(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, |a, b| boolean(a.is_less_than(b)))
+}
+
+pub fn less_or_equal(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, |a, b| boolean(!b.is_less_than(a)))
+}
+
+pub fn greater_or_equal(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, |a, b| boolean(!a.is_less_than(b)))
+}
+
+pub fn greater(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, |a, b| boolean(b.is_less_than(a)))
+}
+
+// The greatest common divisor by Euclid's algorithm. On floats it stops when
+// the remainder is negligible so that it also applies to fractions.
+fn gcd(a: Number, b: Number) -> Number {
+    let exact = |a: i64, b: i64| {
+        let (mut a, mut b) = (a.checked_abs()?, b.checked_abs()?);
+        while b != 0 {
+            (a, b) = (b, a % b);
         }
+        Some(a)
+    };
+    let inexact = |a: f64, b: f64| {
+        let (mut a, mut b) = (a.abs(), b.abs());
+        while b > COMPARISON_TOLERANCE * a {
+            (a, b) = (b, a.rem_euclid(b));
+        }
+        a
+    };
+    a.exact_or(b, exact, inexact)
+}
+
+// The least common multiple, with the sign of the product of the arguments.
+fn lcm(a: Number, b: Number) -> Number {
+    if a.as_f64() == 0. || b.as_f64() == 0. {
+        return Number::int(0);
+    }
+    let divisor = gcd(a, b);
+    match (a.as_int(), b.as_int(), divisor.as_int()) {
+        (Some(a), Some(b), Some(divisor)) => match (a / divisor).checked_mul(b) {
+            Some(n) => Number::int(n),
+            None => Number::float(a as f64 / divisor as f64 * b as f64),
+        },
+        _ => Number::float(a.as_f64() * b.as_f64() / divisor.as_f64()),
     }
 }
 
-pub fn times(alpha: &Value, omega: &Value) -> Result<Value, String> {
-    match (alpha, omega) {
-        (Value::Number(value1), Value::Number(value2)) => {
-            return Ok(Value::Number(value1 * value2));
+// Logical and of booleans, least common multiple of other numbers.
+pub fn and(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, lcm)
+}
+
+// Logical or of booleans, greatest common divisor of other numbers.
+pub fn or(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, gcd)
+}
+
+pub fn nand(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, |a, b| match (a, b) {
+        (Number::Bool(a), Number::Bool(b)) => boolean(!(a && b)),
+        _ => Number::Float(f64::NAN),
+    })
+}
+
+pub fn nor(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    dyadic_scalar(alpha, omega, |a, b| match (a, b) {
+        (Number::Bool(a), Number::Bool(b)) => boolean(!(a || b)),
+        _ => Number::Float(f64::NAN),
+    })
+}
+
+pub fn not(omega: &Value) -> Result<Value, Error> {
+    monadic_scalar(omega, |n| match n {
+        Number::Bool(b) => boolean(!b),
+        _ => Number::Float(f64::NAN),
+    })
+}
+
+// The items of `alpha` that are not found in `omega`.
+pub fn without(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    if alpha.rank() > 1 {
+        return Err(Error::rank(""));
+    }
+    let excluded = omega.ravel();
+    let values = alpha
+        .ravel()
+        .into_iter()
+        .filter(|value| !excluded.iter().any(|other| value.matches(other)))
+        .collect();
+    Ok(Value::vector(values))
+}
+
+/// The identity element of the primitive scalar function `function`, which
+/// is the result of reducing an empty array with it.
+pub fn identity(function: char) -> Option<Value> {
+    let n = match function {
+        '+' | '-' | '|' | '∨' | '≠' | '<' | '>' => Number::int(0),
+        '×' | '÷' | '*' | '!' | '∧' | '=' | '≤' | '≥' => Number::int(1),
+        '⌈' => Number::float(f64::MIN),
+        '⌊' => Number::float(f64::MAX),
+        _ => return None,
+    };
+    Some(Value::Number(n))
+}
+
+/// The largest number of items an array built by a primitive or operator can
+/// have.
+const MAX_LENGTH: usize = 1 << 26;
+
+/// The number of items of an array of shape `shape`, if it is not too large.
+pub fn length(shape: &[usize]) -> Result<usize, Error> {
+    shape
+        .iter()
+        .try_fold(1usize, |length, &n| length.checked_mul(n))
+        .filter(|&length| length <= MAX_LENGTH)
+        .ok_or(Error::limit("array is too large"))
+}
+
+// The length of an axis made of runs of `counts` items, if it can be counted.
+fn summed_length(counts: impl Iterator<Item = u64>) -> Result<usize, Error> {
+    counts
+        .map(usize::try_from)
+        .try_fold(0usize, |length, count| length.checked_add(count.ok()?))
+        .ok_or(Error::limit("array is too large"))
+}
+
+// Read a scalar or vector of non-negative integers, such as a shape.
+fn naturals(value: &Value) -> Result<Vec<usize>, Error> {
+    if value.rank() > 1 {
+        return Err(Error::rank(""));
+    }
+    let mut result: Vec<usize> = Vec::new();
+    for v in value.ravel() {
+        match v {
+            Value::Number(n) if n.as_int().is_some_and(|n| n >= 0) => result.push(n.as_int().unwrap() as usize),
+            _ => return Err(Error::domain("")),
         }
-        (Value::Array(values1), Value::Array(values2)) => {
-            if values1.len() != values2.len() {
-                return Err("Vectors don't have the same rank".to_string());
-            }
-            let mut vector: Vec<Value> = Vec::new();
-            for i in 0..values1.len() {
-                if let (Value::Number(v1), Value::Number(v2)) = (&values1[i], &values2[i]) {
-                    vector.push(Value::Number(v1 * v2))
-                }
-            }
-            return Ok(Value::Array(vector));
-        }
-        (Value::Array(values), Value::Number(value))
-        | (Value::Number(value), Value::Array(values)) => {
-            let mut vector: Vec<Value> = Vec::new();
-            for v in values {
-                if let Value::Number(v1) = &v {
-                    vector.push(Value::Number(v1 * value))
-                }
-            }
-            return Ok(Value::Array(vector));
+    }
+    Ok(result)
+}
+
+// Read a scalar or vector of integers.
+fn integers(value: &Value) -> Result<Vec<i64>, Error> {
+    if value.rank() > 1 {
+        return Err(Error::rank(""));
+    }
+    value
+        .ravel()
+        .iter()
+        .map(|v| match v {
+            Value::Number(n) => n.as_int().ok_or(Error::domain("")),
+            _ => Err(Error::domain("")),
+        })
+        .collect()
+}
+
+/// The vectors along `axis` of an array, in ravel order of the other axes.
+pub fn split_axis(shape: &[usize], values: &[Value], axis: usize) -> Vec<Vec<Value>> {
+    let length = shape[axis];
+    let outer: usize = shape[..axis].iter().product();
+    let inner: usize = shape[axis + 1..].iter().product();
+    let mut vectors: Vec<Vec<Value>> = Vec::with_capacity(outer * inner);
+    for o in 0..outer {
+        for i in 0..inner {
+            let vector = (0..length).map(|k| values[(o * length + k) * inner + i].clone());
+            vectors.push(vector.collect());
         }
-        _ => unreachable!(),
     }
+    vectors
 }
 
-pub fn ceiling(omega: &Value) -> Result<Value, String> {
-    match omega {
-        Value::Number(value) => {
-            return Ok(Value::Number(value.ceil()));
-        }
-        Value::Array(values) => {
-            let mut vector: Vec<Value> = Vec::new();
-            for value in values {
-                if let Value::Number(n) = value {
-                    vector.push(Value::Number(n.ceil()))
-                }
-            }
-            return Ok(Value::Array(vector));
+/// Build an array of shape `shape` from its vectors along `axis`, as given
+/// by `split_axis`.
+pub fn join_axis(shape: Vec<usize>, axis: usize, vectors: Vec<Vec<Value>>) -> Value {
+    let length = shape[axis];
+    let inner: usize = shape[axis + 1..].iter().product();
+    let mut values: Vec<Value> = vec![Value::None; shape.iter().product()];
+    for (v, vector) in vectors.into_iter().enumerate() {
+        let (o, i) = (v / inner, v % inner);
+        for (k, value) in vector.into_iter().enumerate() {
+            values[(o * length + k) * inner + i] = value;
         }
-        _ => {
-            return Err("Domain Error".to_string());
+    }
+    Value::new_array(shape, values)
+}
+
+pub fn shape(omega: &Value) -> Result<Value, Error> {
+    Ok(Value::vector(
+        omega.shape().iter().map(|&n| Value::Number(Number::int(n as i64))).collect(),
+    ))
+}
+
+pub fn reshape(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    let shape = naturals(alpha)?;
+    let length = length(&shape)?;
+    let fill = omega.fill();
+    let mut values = omega.ravel();
+    if values.is_empty() {
+        values.push(fill.clone());
+    }
+    let values: Vec<Value> = values.iter().cycle().take(length).cloned().collect();
+    Ok(Value::new_array(shape, values).with_fill(fill))
+}
+
+pub fn index_generator(omega: &Value, origin: usize) -> Result<Value, Error> {
+    let shape = naturals(omega)?;
+    let length = length(&shape)?;
+    if omega.rank() == 0 {
+        return Ok(Value::vector(
+            (0..length).map(|i| Value::Number(Number::int((i + origin) as i64))).collect(),
+        ));
+    }
+    // Each item is the vector of its own coordinates, last axis varying fastest.
+    let mut values: Vec<Value> = Vec::with_capacity(length);
+    for i in 0..length {
+        let mut index = vec![Value::Number(Number::int(0)); shape.len()];
+        let mut rest = i;
+        for (axis, &n) in shape.iter().enumerate().rev() {
+            index[axis] = Value::Number(Number::int((rest % n + origin) as i64));
+            rest /= n;
         }
+        values.push(Value::vector(index));
+    }
+    Ok(Value::new_array(shape, values))
+}
+
+pub fn index_of(alpha: &Value, omega: &Value, origin: usize) -> Result<Value, Error> {
+    if alpha.rank() != 1 {
+        return Err(Error::rank(""));
     }
+    let haystack = alpha.ravel();
+    let values: Vec<Value> = omega
+        .ravel()
+        .iter()
+        .map(|needle| {
+            let position = haystack
+                .iter()
+                .position(|v| v.matches(needle))
+                .unwrap_or(haystack.len());
+            Value::Number(Number::int((position + origin) as i64))
+        })
+        .collect();
+    Ok(Value::new_array(omega.shape(), values))
 }
 
-pub fn maximum(alpha: &Value, omega: &Value) -> Result<Value, String> {
-    match (alpha, omega) {
-        (Value::Number(value1), Value::Number(value2)) => {
-            return Ok(Value::Number(f32::max(*value1, *value2)));
+// Repeat each item along `axis` of `omega` as many times as the matching
+// number of `alpha` says, or insert as many fill items when it is negative.
+fn replicate_axis(alpha: &Value, omega: &Value, axis: usize) -> Result<Value, Error> {
+    let mut counts = integers(alpha)?;
+    let mut shape = match omega.rank() {
+        0 => vec![1],
+        _ => omega.shape(),
+    };
+    let mut values = omega.ravel();
+    // A single count, or a single item along the axis, is paired with every
+    // item of the other
+    if counts.len() == 1 {
+        counts = vec![counts[0]; shape[axis]];
+    } else if shape[axis] == 1 {
+        let vectors = split_axis(&shape, &values, axis);
+        shape[axis] = counts.len();
+        let vectors = vectors
+            .into_iter()
+            .map(|vector| vec![vector[0].clone(); counts.len()])
+            .collect();
+        values = join_axis(shape.clone(), axis, vectors).ravel();
+    }
+    if counts.len() != shape[axis] {
+        return Err(Error::length(""));
+    }
+    let mut result_shape = shape.clone();
+    result_shape[axis] = summed_length(counts.iter().map(|count| count.unsigned_abs()))?;
+    length(&result_shape)?;
+    let fill = omega.fill();
+    let mut vectors: Vec<Vec<Value>> = Vec::new();
+    for vector in split_axis(&shape, &values, axis) {
+        let mut replicated: Vec<Value> = Vec::new();
+        for (item, &count) in vector.iter().zip(&counts) {
+            let item = if count < 0 { &fill } else { item };
+            replicated.extend(std::iter::repeat_n(item.clone(), count.unsigned_abs() as usize));
         }
-        (Value::Array(values1), Value::Array(values2)) => {
-            if values1.len() != values2.len() {
-                return Err("Vectors don't have the same rank".to_string());
-            }
-            let mut vector: Vec<Value> = Vec::new();
-            for i in 0..values1.len() {
-                if let (Value::Number(v1), Value::Number(v2)) = (&values1[i], &values2[i]) {
-                    vector.push(Value::Number(f32::max(*v1, *v2)))
-                }
+        vectors.push(replicated);
+    }
+    Ok(join_axis(result_shape, axis, vectors).with_fill(fill))
+}
+
+pub fn replicate(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    replicate_axis(alpha, omega, omega.rank().max(1) - 1)
+}
+
+pub fn replicate_first(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    replicate_axis(alpha, omega, 0)
+}
+
+// Insert fill items in the vectors along `axis` of `omega`: each positive
+// number of `alpha` repeats the next item that many times, and any other
+// number inserts as many fill items, at least one.
+fn expand_axis(alpha: &Value, omega: &Value, axis: usize) -> Result<Value, Error> {
+    let counts = integers(alpha)?;
+    let shape = match omega.rank() {
+        0 => vec![1],
+        _ => omega.shape(),
+    };
+    let values = omega.ravel();
+    let used = counts.iter().filter(|&&count| count > 0).count();
+    if used != shape[axis] && shape[axis] != 1 {
+        return Err(Error::length(""));
+    }
+    let mut result_shape = shape.clone();
+    result_shape[axis] = summed_length(counts.iter().map(|count| count.unsigned_abs().max(1)))?;
+    length(&result_shape)?;
+    let fill = omega.fill();
+    let mut vectors: Vec<Vec<Value>> = Vec::new();
+    for vector in split_axis(&shape, &values, axis) {
+        let mut items = vector.iter().cycle();
+        let mut expanded: Vec<Value> = Vec::new();
+        for &count in &counts {
+            if count > 0 {
+                let item = items.next().ok_or(Error::length(""))?;
+                expanded.extend(std::iter::repeat_n(item.clone(), count as usize));
+            } else {
+                let fills = count.unsigned_abs().max(1) as usize;
+                expanded.extend(std::iter::repeat_n(fill.clone(), fills));
             }
-            return Ok(Value::Array(vector));
         }
-        _ => unreachable!(),
+        vectors.push(expanded);
     }
+    Ok(join_axis(result_shape, axis, vectors).with_fill(fill))
 }
 
-pub fn floor(omega: &Value) -> Result<Value, String> {
-    match omega {
-        Value::Number(value) => {
-            return Ok(Value::Number(value.floor()));
-        }
-        Value::Array(values) => {
-            let mut vector: Vec<Value> = Vec::new();
-            for value in values {
-                if let Value::Number(n) = value {
-                    vector.push(Value::Number(n.floor()))
+pub fn expand(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    expand_axis(alpha, omega, omega.rank().max(1) - 1)
+}
+
+pub fn expand_first(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    expand_axis(alpha, omega, 0)
+}
+
+pub fn ravel(omega: &Value) -> Result<Value, Error> {
+    Ok(Value::vector(omega.ravel()).with_fill(omega.fill()))
+}
+
+// `omega` as a matrix whose rows are its major cells.
+pub fn table(omega: &Value) -> Result<Value, Error> {
+    let shape = omega.shape();
+    let rows = shape.first().copied().unwrap_or(1);
+    let columns = shape.iter().skip(1).product();
+    Ok(Value::new_array(vec![rows, columns], omega.ravel()).with_fill(omega.fill()))
+}
+
+// Every simple scalar of `omega`, at any depth, in a vector.
+pub fn enlist(omega: &Value) -> Result<Value, Error> {
+    fn simple_scalars(value: &Value, result: &mut Vec<Value>) {
+        match value {
+            Value::Array { values, .. } => {
+                for value in values {
+                    simple_scalars(value, result);
                 }
             }
-            return Ok(Value::Array(vector));
+            _ => result.push(value.clone()),
+        }
+    }
+    let mut result: Vec<Value> = Vec::new();
+    simple_scalars(omega, &mut result);
+    Ok(Value::vector(result))
+}
+
+/// Join `alpha` and `omega` along `axis`. An argument of lower rank, or a
+/// scalar, stands for a single cell along the axis.
+pub fn catenate_axis(alpha: &Value, omega: &Value, axis: usize) -> Result<Value, Error> {
+    let rank = alpha.rank().max(omega.rank()).max(1);
+    if axis >= rank {
+        return Err(Error::axis(""));
+    }
+    // The shape of an argument as an array of full rank
+    let cell_shape = |value: &Value, other: &Value| -> Result<Vec<usize>, Error> {
+        let shape = value.shape();
+        if shape.len() == rank {
+            return Ok(shape);
         }
-        _ => {
-            return Err("Domain Error".to_string());
+        let mut shape = match value.rank() {
+            0 => other.shape(),
+            _ => shape,
+        };
+        if shape.len() == rank {
+            shape[axis] = 1;
+        } else if shape.len() + 1 == rank {
+            shape.insert(axis, 1);
+        } else {
+            return Err(Error::rank(""));
         }
+        Ok(shape)
+    };
+    let shape1 = cell_shape(alpha, omega)?;
+    let shape2 = cell_shape(omega, alpha)?;
+    let mut others1 = shape1.clone();
+    let mut others2 = shape2.clone();
+    others1.remove(axis);
+    others2.remove(axis);
+    if others1 != others2 {
+        return Err(Error::length(""));
     }
+    let inner: usize = shape1[axis + 1..].iter().product();
+    let (chunk1, chunk2) = (shape1[axis] * inner, shape2[axis] * inner);
+    // A scalar is repeated to fill its cell
+    let fill = |value: &Value, shape: &[usize]| -> Vec<Value> {
+        value.ravel().into_iter().cycle().take(shape.iter().product()).collect()
+    };
+    let (values1, values2) = (fill(alpha, &shape1), fill(omega, &shape2));
+    let outer: usize = shape1[..axis].iter().product();
+    let mut values: Vec<Value> = Vec::with_capacity(values1.len() + values2.len());
+    for o in 0..outer {
+        values.extend_from_slice(&values1[o * chunk1..(o + 1) * chunk1]);
+        values.extend_from_slice(&values2[o * chunk2..(o + 1) * chunk2]);
+    }
+    let mut shape = shape1;
+    shape[axis] += shape2[axis];
+    Ok(Value::new_array(shape, values).with_fill(alpha.fill()))
+}
+
+pub fn catenate(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    let rank = alpha.rank().max(omega.rank()).max(1);
+    catenate_axis(alpha, omega, rank - 1)
+}
+
+pub fn catenate_first(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    catenate_axis(alpha, omega, 0)
+}
+
+/// Join `alpha` and `omega`, which must have the same shape, along a new axis
+/// inserted before `axis`. A scalar is paired with every item of the other.
+pub fn laminate(alpha: &Value, omega: &Value, axis: usize) -> Result<Value, Error> {
+    let shape = match (alpha.rank(), omega.rank()) {
+        (0, _) => omega.shape(),
+        (_, 0) => alpha.shape(),
+        _ if alpha.shape() == omega.shape() => alpha.shape(),
+        (rank1, rank2) if rank1 != rank2 => return Err(Error::rank("")),
+        _ => return Err(Error::length("")),
+    };
+    if axis > shape.len() {
+        return Err(Error::axis(""));
+    }
+    let mut cell_shape = shape.clone();
+    cell_shape.insert(axis, 1);
+    let cell = |value: &Value| {
+        let values = value.ravel().into_iter().cycle().take(shape.iter().product()).collect();
+        Value::new_array(cell_shape.clone(), values).with_fill(value.fill())
+    };
+    catenate_axis(&cell(alpha), &cell(omega), axis)
+}
+
+/// `omega` with a new axis of length 1 inserted before `axis`.
+pub fn ravel_new_axis(omega: &Value, axis: usize) -> Result<Value, Error> {
+    let mut shape = omega.shape();
+    if axis > shape.len() {
+        return Err(Error::axis(""));
+    }
+    shape.insert(axis, 1);
+    Ok(Value::new_array(shape, omega.ravel()).with_fill(omega.fill()))
 }
 
-pub fn minimum(alpha: &Value, omega: &Value) -> Result<Value, String> {
-    match (alpha, omega) {
-        (Value::Number(value1), Value::Number(value2)) => {
-            return Ok(Value::Number(f32::min(*value1, *value2)));
+// The array of shape `shape` whose item at each index is the item of `omega`
+// at that index shifted by `offsets`, or a fill item when there is none.
+fn shifted(
+    omega: &Value,
+    source_shape: &[usize],
+    shape: Vec<usize>,
+    offsets: &[i64],
+) -> Result<Value, Error> {
+    let length = length(&shape)?;
+    let source = omega.ravel();
+    let fill = omega.fill();
+    let mut values: Vec<Value> = Vec::with_capacity(length);
+    for i in 0..length {
+        // Walk the axes from the last, locating the item in the source
+        let (mut rest, mut position, mut stride, mut inside) = (i, 0, 1, true);
+        for axis in (0..shape.len()).rev() {
+            let index = (rest % shape[axis]) as i64 + offsets[axis];
+            rest /= shape[axis];
+            inside &= 0 <= index && index < source_shape[axis] as i64;
+            position += index.max(0) as usize * stride;
+            stride *= source_shape[axis];
         }
-        (Value::Array(values1), Value::Array(values2)) => {
-            if values1.len() != values2.len() {
-                return Err("Vectors don't have the same rank".to_string());
-            }
-            let mut vector: Vec<Value> = Vec::new();
-            for i in 0..values1.len() {
-                if let (Value::Number(v1), Value::Number(v2)) = (&values1[i], &values2[i]) {
-                    vector.push(Value::Number(f32::min(*v1, *v2)))
-                }
-            }
-            return Ok(Value::Array(vector));
+        values.push(if inside { source[position].clone() } else { fill.clone() });
+    }
+    Ok(Value::new_array(shape, values).with_fill(fill))
+}
+
+// The counts of `alpha` for the leading axes of `omega`, with the shape of
+// `omega` as an array of at least as many axes.
+fn axis_counts(alpha: &Value, omega: &Value) -> Result<(Vec<i64>, Vec<usize>), Error> {
+    let counts = integers(alpha)?;
+    let shape = match omega.rank() {
+        0 => vec![1; counts.len()],
+        _ => omega.shape(),
+    };
+    if counts.len() > shape.len() {
+        return Err(Error::rank(""));
+    }
+    Ok((counts, shape))
+}
+
+// Take the first items along each axis for a positive count, the last ones
+// for a negative count, padding with fill items beyond the ends.
+pub fn take(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    let (counts, source_shape) = axis_counts(alpha, omega)?;
+    let mut shape = source_shape.clone();
+    let mut offsets = vec![0; shape.len()];
+    for (axis, &count) in counts.iter().enumerate() {
+        shape[axis] = count.unsigned_abs() as usize;
+        if count < 0 {
+            offsets[axis] = source_shape[axis] as i64 + count;
+        }
+    }
+    shifted(omega, &source_shape, shape, &offsets)
+}
+
+// Drop the first items along each axis for a positive count, the last ones
+// for a negative count.
+pub fn drop(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    let (counts, source_shape) = axis_counts(alpha, omega)?;
+    let mut shape = source_shape.clone();
+    let mut offsets = vec![0; shape.len()];
+    for (axis, &count) in counts.iter().enumerate() {
+        shape[axis] = source_shape[axis].saturating_sub(count.unsigned_abs() as usize);
+        offsets[axis] = count.max(0);
+    }
+    shifted(omega, &source_shape, shape, &offsets)
+}
+
+/// Reverse the order of the items of `omega` along `axis`.
+pub fn reverse_axis(omega: &Value, axis: usize) -> Result<Value, Error> {
+    if omega.rank() == 0 {
+        return Ok(omega.clone());
+    }
+    if axis >= omega.rank() {
+        return Err(Error::axis(""));
+    }
+    let shape = omega.shape();
+    let mut vectors = split_axis(&shape, &omega.ravel(), axis);
+    for vector in &mut vectors {
+        vector.reverse();
+    }
+    Ok(join_axis(shape, axis, vectors).with_fill(omega.fill()))
+}
+
+pub fn reverse(omega: &Value) -> Result<Value, Error> {
+    reverse_axis(omega, omega.rank().max(1) - 1)
+}
+
+pub fn reverse_first(omega: &Value) -> Result<Value, Error> {
+    reverse_axis(omega, 0)
+}
+
+/// Rotate the vectors along `axis` of `omega` to the left by the amounts of
+/// `alpha`, which is either a single amount or one amount for each vector.
+pub fn rotate_axis(alpha: &Value, omega: &Value, axis: usize) -> Result<Value, Error> {
+    if omega.rank() == 0 {
+        integers(alpha)?;
+        return Ok(omega.clone());
+    }
+    if axis >= omega.rank() {
+        return Err(Error::axis(""));
+    }
+    let shape = omega.shape();
+    let mut others = shape.clone();
+    others.remove(axis);
+    let amounts = match alpha.ravel().len() {
+        1 => vec![integers(&Value::vector(alpha.ravel()))?[0]; others.iter().product()],
+        _ if alpha.shape() == others => integers(&Value::vector(alpha.ravel()))?,
+        _ if alpha.rank() != others.len() => return Err(Error::rank("")),
+        _ => return Err(Error::length("")),
+    };
+    let mut vectors = split_axis(&shape, &omega.ravel(), axis);
+    for (vector, amount) in vectors.iter_mut().zip(amounts) {
+        if !vector.is_empty() {
+            let length = vector.len() as i64;
+            vector.rotate_left(amount.rem_euclid(length) as usize);
+        }
+    }
+    Ok(join_axis(shape, axis, vectors).with_fill(omega.fill()))
+}
+
+pub fn rotate(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    rotate_axis(alpha, omega, omega.rank().max(1) - 1)
+}
+
+pub fn rotate_first(alpha: &Value, omega: &Value) -> Result<Value, Error> {
+    rotate_axis(alpha, omega, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::repl::evaluate;
+
+    fn number(n: i64) -> Value {
+        Value::Number(Number::int(n))
+    }
+
+    #[test]
+    fn singletons_pair_with_empty_arrays() {
+        let empty = Value::vector(Vec::new());
+        let one = Value::vector(vec![number(1)]);
+        for (alpha, omega) in [(&number(1), &empty), (&empty, &number(1)), (&one, &empty)] {
+            assert!(plus(alpha, omega).unwrap().matches(&empty));
+            assert!(equal(alpha, omega).unwrap().matches(&empty));
+            assert!(logarithm(alpha, omega).unwrap().matches(&empty));
+        }
+        let matrix = Value::new_array(vec![2, 0], Vec::new());
+        assert!(times(&matrix, &number(2)).unwrap().matches(&matrix));
+    }
+
+    #[test]
+    fn singletons_pair_with_every_item() {
+        let vector = Value::vector(vec![number(1), number(2)]);
+        let sum = Value::vector(vec![number(11), number(12)]);
+        assert!(plus(&number(10), &vector).unwrap().matches(&sum));
+        assert!(plus(&vector, &Value::vector(vec![number(10)])).unwrap().matches(&sum));
+        let error = plus(&vector, &Value::vector(vec![number(1), number(2), number(3)])).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Length);
+    }
+
+    #[test]
+    fn empty_arrays_fill_with_their_prototype() {
+        let empty = Value::vector(Vec::new()).with_fill(Value::Char(' '));
+        let blanks = Value::vector(vec![Value::Char(' '); 3]);
+        assert!(reshape(&number(3), &empty).unwrap().matches(&blanks));
+        assert!(take(&number(3), &empty).unwrap().matches(&blanks));
+        let dropped = drop(&number(1), &Value::Char('a')).unwrap();
+        assert!(take(&number(-3), &dropped).unwrap().matches(&blanks));
+        assert!(expand(&Value::vector(vec![number(0); 3]), &empty).unwrap().matches(&blanks));
+        assert!(take(&number(3), &ravel(&reverse(&empty).unwrap()).unwrap()).unwrap().matches(&blanks));
+        assert!(!empty.matches(&Value::vector(Vec::new())));
+    }
+
+    #[test]
+    fn replications_too_large_are_a_limit_error() {
+        for count in [100000000, 9000000000000000000, -9000000000000000000] {
+            let error = replicate(&number(count), &number(1)).unwrap_err();
+            assert_eq!(error.kind, ErrorKind::Limit);
+        }
+        let counts = Value::vector(vec![number(i64::MAX), number(i64::MAX), number(i64::MAX)]);
+        let error = replicate_first(&counts, &Value::vector(vec![number(1); 3])).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Limit);
+    }
+
+    #[test]
+    fn expansions_too_large_are_a_limit_error() {
+        for count in [100000000, 9000000000000000000, -9000000000000000000] {
+            let error = expand(&number(count), &number(1)).unwrap_err();
+            assert_eq!(error.kind, ErrorKind::Limit);
+        }
+        let counts = Value::vector(vec![number(i64::MAX), number(i64::MAX), number(i64::MAX)]);
+        let error = expand(&counts, &number(1)).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Limit);
+    }
+
+    #[test]
+    fn division_by_zero_is_only_defined_for_zero() {
+        assert_eq!(evaluate("0÷0"), Ok("1".to_string()));
+        for source in ["1÷0", "÷0", "¯2÷0 1"] {
+            assert_eq!(evaluate(source).unwrap_err().kind, ErrorKind::Domain);
         }
-        _ => unreachable!(),
+    }
+
+    #[test]
+    fn residues_take_the_sign_of_the_left_argument() {
+        assert_eq!(evaluate("3|¯7 7"), Ok("2 1".to_string()));
+        assert_eq!(evaluate("¯3|7 ¯7"), Ok("¯2 ¯1".to_string()));
+        assert_eq!(evaluate("0|5"), Ok("5".to_string()));
+        assert_eq!(evaluate("2.5|7"), Ok("2".to_string()));
+    }
+
+    #[test]
+    fn powers_and_logarithms() {
+        assert_eq!(evaluate("0*0"), Ok("1".to_string()));
+        assert_eq!(evaluate("2*10"), Ok("1024".to_string()));
+        assert_eq!(evaluate("¯8*÷3"), Ok("1J1.732050808".to_string()));
+        assert_eq!(evaluate("2⍟8"), Ok("3".to_string()));
+        assert_eq!(evaluate("0⍟1"), Ok("0".to_string()));
+        for source in ["⍟0", "1⍟2"] {
+            assert_eq!(evaluate(source).unwrap_err().kind, ErrorKind::Domain);
+        }
+    }
+
+    #[test]
+    fn factorials_past_integers_are_floats() {
+        assert_eq!(evaluate("!20"), Ok("2432902008176640000".to_string()));
+        assert_eq!(evaluate("!21"), Ok("5.109094217E19".to_string()));
+        assert_eq!(evaluate("!3.5"), Ok("11.6317284".to_string()));
+        assert_eq!(evaluate("!171").unwrap_err().kind, ErrorKind::Domain);
+    }
+
+    #[test]
+    fn binomials_of_negative_arguments() {
+        assert_eq!(evaluate("2!5"), Ok("10".to_string()));
+        assert_eq!(evaluate("3!2"), Ok("0".to_string()));
+        assert_eq!(evaluate("2!¯3"), Ok("6".to_string()));
+        assert_eq!(evaluate("1!¯2"), Ok("¯2".to_string()));
+        assert_eq!(evaluate("¯1!3"), Ok("0".to_string()));
+        assert_eq!(evaluate("¯2!¯3"), Ok("0".to_string()));
+    }
+
+    #[test]
+    fn circular_functions() {
+        assert_eq!(evaluate("1○○÷2"), Ok("1".to_string()));
+        assert_eq!(evaluate("2○0"), Ok("1".to_string()));
+        assert_eq!(evaluate("¯1○1"), Ok("1.570796327".to_string()));
+        assert_eq!(evaluate("4○0"), Ok("1".to_string()));
+        assert_eq!(evaluate("¯4○2"), Ok("1.732050808".to_string()));
+        assert_eq!(evaluate("¯4○0.5"), Ok("0J0.8660254038".to_string()));
+        assert_eq!(evaluate("12○0J1"), Ok("1.570796327".to_string()));
+        assert_eq!(evaluate("12○¯1"), Ok("3.141592654".to_string()));
+        assert_eq!(evaluate("¯12○1"), Ok("0.5403023059J0.8414709848".to_string()));
+        assert_eq!(evaluate("13○1").unwrap_err().kind, ErrorKind::Domain);
+    }
+
+    #[test]
+    fn comparisons_are_tolerant() {
+        assert_eq!(evaluate("1=1+1E¯14"), Ok("1".to_string()));
+        assert_eq!(evaluate("1=1+1E¯13"), Ok("0".to_string()));
+        assert_eq!(evaluate("3≠3.00000000000001"), Ok("0".to_string()));
+        assert_eq!(evaluate("0=1E¯20"), Ok("0".to_string()));
+        assert_eq!(evaluate("1 2 3~1+1E¯15"), Ok("2 3".to_string()));
+    }
+
+    #[test]
+    fn gcd_and_lcm_extend_past_booleans() {
+        assert_eq!(evaluate("12∨18"), Ok("6".to_string()));
+        assert_eq!(evaluate("12∧18"), Ok("36".to_string()));
+        assert_eq!(evaluate("¯12∨18"), Ok("6".to_string()));
+        assert_eq!(evaluate("¯4∧6"), Ok("¯12".to_string()));
+        assert_eq!(evaluate("0∨5"), Ok("5".to_string()));
+        assert_eq!(evaluate("0∧5"), Ok("0".to_string()));
+        assert_eq!(evaluate("1.5∨2.5"), Ok("0.5".to_string()));
+        assert_eq!(evaluate("0.5∧0.75"), Ok("1.5".to_string()));
+        assert_eq!(evaluate("'a'∨1").unwrap_err().kind, ErrorKind::Domain);
+    }
+
+    #[test]
+    fn tilde_is_not_and_without() {
+        assert_eq!(evaluate("~1 0"), Ok("0 1".to_string()));
+        assert_eq!(evaluate("~2").unwrap_err().kind, ErrorKind::Domain);
+        assert_eq!(evaluate("1 2 3 4~2 4"), Ok("1 3".to_string()));
+        assert_eq!(evaluate("'hello'~'l'"), Ok("heo".to_string()));
+    }
+
+    #[test]
+    fn characters_compare_for_equality_only() {
+        assert_eq!(evaluate("'a'='abc'"), Ok("1 0 0".to_string()));
+        assert_eq!(evaluate("'abc'='abd'"), Ok("1 1 0".to_string()));
+        assert_eq!(evaluate("'a'≠1"), Ok("1".to_string()));
+        assert_eq!(evaluate("'1'=1"), Ok("0".to_string()));
+        assert_eq!(evaluate("'a'<'b'").unwrap_err().kind, ErrorKind::Domain);
+    }
+
+    #[test]
+    fn take_and_drop_along_several_axes() {
+        assert_eq!(evaluate("2 2↑3 3⍴⍳9"), Ok("1 2\n4 5".to_string()));
+        assert_eq!(evaluate("¯2 ¯1↑3 3⍴⍳9"), Ok("6\n9".to_string()));
+        assert_eq!(evaluate("1 1↓3 3⍴⍳9"), Ok("5 6\n8 9".to_string()));
+        assert_eq!(evaluate("¯1 ¯2↓3 3⍴⍳9"), Ok("1\n4".to_string()));
+        assert_eq!(evaluate("1 2 3↑2 2⍴1").unwrap_err().kind, ErrorKind::Rank);
+    }
+
+    #[test]
+    fn overtaking_pads_with_fill_items() {
+        assert_eq!(evaluate("5↑1 2"), Ok("1 2 0 0 0".to_string()));
+        assert_eq!(evaluate("¯5↑1 2"), Ok("0 0 0 1 2".to_string()));
+        assert_eq!(evaluate("2 3↑2 2⍴⍳4"), Ok("1 2 0\n3 4 0".to_string()));
+        assert_eq!(evaluate("1 2↑5"), Ok("5 0".to_string()));
+        assert_eq!(evaluate("2↑⍳0"), Ok("0 0".to_string()));
+        assert_eq!(evaluate("¯4↑'ab'"), Ok("  ab".to_string()));
+        assert_eq!(evaluate("⍴¯7↓⍳3"), Ok("0".to_string()));
+    }
+
+    #[test]
+    fn rotations_by_vector_or_per_row() {
+        assert_eq!(evaluate("¯1⌽⍳4"), Ok("4 1 2 3".to_string()));
+        assert_eq!(evaluate("1 2⌽2 3⍴⍳6"), Ok("2 3 1\n6 4 5".to_string()));
+        assert_eq!(evaluate("1 ¯1⊖3 2⍴⍳6"), Ok("3 6\n5 2\n1 4".to_string()));
+        assert_eq!(evaluate("1 2 3⌽2 3⍴⍳6").unwrap_err().kind, ErrorKind::Length);
+    }
+
+    #[test]
+    fn scalars_catenate_as_a_cell_of_matrices() {
+        assert_eq!(evaluate("0,2 2⍴⍳4"), Ok("0 1 2\n0 3 4".to_string()));
+        assert_eq!(evaluate("(2 2⍴⍳4),0"), Ok("1 2 0\n3 4 0".to_string()));
+        assert_eq!(evaluate("0⍪2 2⍴⍳4"), Ok("0 0\n1 2\n3 4".to_string()));
+        assert_eq!(evaluate("(2 2⍴⍳4),1 2 3").unwrap_err().kind, ErrorKind::Length);
+    }
+
+    #[test]
+    fn catenating_along_the_first_axis() {
+        assert_eq!(evaluate("(2 2⍴⍳4)⍪5 6"), Ok("1 2\n3 4\n5 6".to_string()));
+        assert_eq!(evaluate("(2 2⍴⍳4),[1]5 6"), Ok("1 2\n3 4\n5 6".to_string()));
+        assert_eq!(evaluate("1 2⍪3 4"), Ok("1 2 3 4".to_string()));
+    }
+
+    #[test]
+    fn enlist_flattens_nested_arrays() {
+        assert_eq!(evaluate("∊(1 2)(3 (4 5))"), Ok("1 2 3 4 5".to_string()));
+        assert_eq!(evaluate("⍴∊'ab'(1 2)"), Ok("4".to_string()));
+        assert_eq!(evaluate("⍴∊5"), Ok("1".to_string()));
+    }
+
+    #[test]
+    fn laminating_inserts_a_new_axis() {
+        assert_eq!(evaluate("1 2,[0.5]3 4"), Ok("1 2\n3 4".to_string()));
+        assert_eq!(evaluate("1 2,[1.5]3 4"), Ok("1 3\n2 4".to_string()));
+        assert_eq!(evaluate("⍴1 2 3,[0.5]4"), Ok("2 3".to_string()));
+        assert_eq!(evaluate("1 2,[2.5]3 4").unwrap_err().kind, ErrorKind::Axis);
+    }
+
+    #[test]
+    fn shapes_of_scalars_vectors_and_matrices() {
+        assert_eq!(evaluate("⍴2 3⍴⍳6"), Ok("2 3".to_string()));
+        assert_eq!(evaluate("⍴⍬"), Ok("0".to_string()));
+        assert_eq!(evaluate("⍴⍴5"), Ok("0".to_string()));
+    }
+
+    #[test]
+    fn reshaping_cycles_through_the_items() {
+        assert_eq!(evaluate("2 3⍴1 2"), Ok("1 2 1\n2 1 2".to_string()));
+        assert_eq!(evaluate("⍬⍴1 2 3"), Ok("1".to_string()));
+        assert_eq!(evaluate("⍴⍴⍬⍴1 2 3"), Ok("0".to_string()));
+        assert_eq!(evaluate("⍴2 0⍴1"), Ok("2 0".to_string()));
+        assert_eq!(evaluate("2 2⍴⍬"), Ok("0 0\n0 0".to_string()));
+        for source in ["¯1⍴1", "2.5⍴1"] {
+            assert_eq!(evaluate(source).unwrap_err().kind, ErrorKind::Domain);
+        }
+    }
+
+    #[test]
+    fn index_generator_follows_the_index_origin() {
+        assert_eq!(evaluate("⍳5"), Ok("1 2 3 4 5".to_string()));
+        assert_eq!(evaluate("⍴⍳0"), Ok("0".to_string()));
+        assert_eq!(evaluate("⎕IO←0 ⋄ ⍳3"), Ok("0 1 2".to_string()));
+        assert_eq!(evaluate("⍳¯1").unwrap_err().kind, ErrorKind::Domain);
+        assert_eq!(evaluate("⎕IO←2").unwrap_err().kind, ErrorKind::Domain);
+    }
+
+    #[test]
+    fn index_generator_of_a_vector_gives_coordinates() {
+        assert_eq!(evaluate("⍴⍳2 3"), Ok("2 3".to_string()));
+        assert_eq!(evaluate("∊⍳2 2"), Ok("1 1 1 2 2 1 2 2".to_string()));
+        assert_eq!(evaluate("⎕IO←0 ⋄ ∊⍳2 2"), Ok("0 0 0 1 1 0 1 1".to_string()));
+    }
+
+    #[test]
+    fn index_of_finds_first_occurrences() {
+        assert_eq!(evaluate("3 1 4 1⍳1 5"), Ok("2 5".to_string()));
+        assert_eq!(evaluate("'abc'⍳'cz'"), Ok("3 4".to_string()));
+        assert_eq!(evaluate("⎕IO←0 ⋄ 3 1 4⍳1 5"), Ok("1 3".to_string()));
+        assert_eq!(evaluate("(2 2⍴1)⍳1").unwrap_err().kind, ErrorKind::Rank);
     }
 }
@@ -1,219 +1,810 @@
 use crate::node::visit::{Acceptor, Visitor};
+use crate::error::Error;
 use crate::node::Node;
-use crate::token::Token;
-use crate::value::{Value, DyadicFunctionHolder, MonadicFunctionHolder};
+use crate::number::Number;
+use crate::token::{Span, Token};
+use crate::value::{Value, DerivedFunctionHolder, DfnHolder, DyadicFunctionHolder, MonadicFunctionHolder};
+use std::collections::{HashMap, HashSet};
+use std::cell::RefCell;
 use std::rc::Rc;
 use crate::functions::*;
 
+/// The deepest nesting of dfn calls allowed.
+const MAX_DEPTH: usize = 1000;
+
+/// A stack size with room for `MAX_DEPTH` nested dfn calls in an
+/// unoptimised build, for the thread the interpreter runs on.
+pub const STACK_SIZE: usize = 256 << 20;
+
+/// The stack an interpreter assumes it has unless told otherwise: the size of
+/// threads spawned by the standard library.
+const DEFAULT_STACK_SIZE: usize = 2 << 20;
+
+/// The variables of the global scope or of a dfn call.
+///
+/// Dfns are lexically scoped: the scope of a call is chained to the scope the
+/// dfn was defined in, not to the scope of its caller.
+pub struct Scope {
+    variables: RefCell<HashMap<String, Value>>,
+    parent: Option<Rc<Scope>>,
+}
+
+impl Scope {
+    fn new(parent: Option<Rc<Scope>>) -> Rc<Scope> {
+        Rc::new(Scope {
+            variables: RefCell::new(HashMap::new()),
+            parent,
+        })
+    }
+
+    fn get(&self, name: &str) -> Option<Value> {
+        self.variables.borrow().get(name).cloned()
+    }
+}
+
+/// Names bound by each dfn call, which are never looked up in enclosing
+/// scopes.
+const ARGUMENTS: [&str; 3] = ["⍺", "⍵", "∇"];
 
 pub struct Interpreter {
-    // environment: Vec<Node>,
+    globals: Rc<Scope>,
+    /// The innermost scope, that of the dfn being run or the global one.
+    scope: Rc<Scope>,
+    /// The index origin (`⎕IO`), 0 or 1, used by primitives that produce or
+    /// consume indices.
+    index_origin: usize,
+    /// How many dfn calls are in progress, to stop runaway recursion before
+    /// it overflows the stack.
+    depth: usize,
+    /// The size of the stack of the thread the interpreter runs on.
+    stack_size: usize,
+    /// The stack position when `interpret` was entered, from which the stack
+    /// used by evaluation is measured.
+    stack_base: usize,
+    /// The command-line arguments given to the program, returned by `⎕ARG`.
+    arguments: Vec<String>,
 }
 
 impl Interpreter {
     pub fn new() -> Interpreter {
+        let globals = Scope::new(None);
         Self {
-            // environment: Vec::new(),
+            globals: globals.clone(),
+            scope: globals,
+            index_origin: 1,
+            depth: 0,
+            stack_size: DEFAULT_STACK_SIZE,
+            stack_base: 0,
+            arguments: Vec::new(),
         }
     }
 
-    pub fn interpret(&mut self, node: &Node) -> Result<Value, String> {
-        self.visit_node(node, None)
+    /// Tell the interpreter how large the stack of its thread is.
+    ///
+    /// Evaluation recurses on the native stack, so deep recursion raises a
+    /// LIMIT ERROR before using more than three quarters of `stack_size`.
+    /// The interpreter assumes 2 MiB, which allows fewer than `MAX_DEPTH`
+    /// nested dfn calls in an unoptimised build. It must not be run on a
+    /// thread with less stack than it is told about.
+    pub fn set_stack_size(&mut self, stack_size: usize) {
+        self.stack_size = stack_size;
     }
 
-    fn visit_node(&mut self, node: &Node, valence: Option<i32>) -> Result<Value, String> {
-        node.accept(self, valence)
+    pub fn set_arguments(&mut self, arguments: Vec<String>) {
+        self.arguments = arguments;
     }
 
+    /// Evaluate `node` and return its value, without displaying anything.
+    pub fn interpret(&mut self, node: &Node) -> Result<Value, Error> {
+        self.stack_base = stack_position();
+        self.visit_node(node, None)
+    }
 
-}
+    fn visit_node(&mut self, node: &Node, valence: Option<i32>) -> Result<Value, Error> {
+        // Stacks grow downwards on every platform Rust supports
+        if self.stack_base.saturating_sub(stack_position()) > self.stack_size / 4 * 3 {
+            return Err(Error::limit("maximum recursion depth exceeded").at(node.span()));
+        }
+        node.accept(self, valence).map_err(|error| error.at(node.span()))
+    }
 
-impl Visitor<Result<Value, String>> for Interpreter {
-    fn visit_scalar(&mut self, token: &Token) -> Result<Value, String> {
-        match token {
-            Token::Number(value) => Ok(Value::Number(*value)),
-            _ => Err("scalar must be a number".to_string()),
+    /// The names of the global variables bound to functions.
+    pub fn function_names(&self) -> HashSet<String> {
+        self.globals
+            .variables
+            .borrow()
+            .iter()
+            .filter(|(_, value)| value.is_function())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn lookup(&self, name: &str) -> Result<Value, Error> {
+        match name {
+            "⎕IO" => return Ok(Value::Number(Number::int(self.index_origin as i64))),
+            "⎕ARG" => {
+                return Ok(Value::vector(
+                    self.arguments
+                        .iter()
+                        .map(|argument| Value::vector(argument.chars().map(Value::Char).collect()))
+                        .collect(),
+                ))
+            }
+            _ => {}
         }
+        let mut scope = Some(&self.scope);
+        while let Some(current) = scope {
+            if let Some(value) = current.get(name) {
+                return Ok(value);
+            }
+            if ARGUMENTS.contains(&name) {
+                break;
+            }
+            scope = current.parent.as_ref();
+        }
+        Err(Error::value(&format!("{} is not defined", name)))
     }
 
-    fn visit_array(&mut self, values: &Vec<Node>) -> Result<Value, String> {
-        let mut array: Vec<Value> = Vec::new();
-        for value in values {
-            array.push(self.visit_node(value, None)?)
+    fn assign(&mut self, name: &str, value: Value) -> Result<(), Error> {
+        if name == "⎕IO" {
+            match value {
+                Value::Number(Number::Bool(b)) => self.index_origin = b as usize,
+                _ => return Err(Error::domain("⎕IO must be 0 or 1")),
+            }
+            return Ok(());
+        }
+        if name.starts_with('⎕') {
+            return Err(Error::syntax(&format!("unknown system variable {}", name)));
         }
-        Ok(Value::Array(array))
+        self.scope.variables.borrow_mut().insert(name.to_string(), value);
+        Ok(())
     }
 
-    // Evaluate a function that have only one argument
-    fn visit_monad(&mut self, operator: &Node, right: &Node) -> Result<Value, String> {
-        let omega = &value_to_node(&self.visit_node(right, Some(1))?);
-        match operator{
-            Node::MonadicOperator{operator, child} => {
-                match operator{
-                    Token::MonadicOperator('⍨') => {
-                        return self.visit_dyad(omega, child.as_ref().unwrap(), omega);
+    // Apply the function described by `function` to a single argument,
+    // locating errors at the function
+    fn apply_monad(&mut self, function: &Node, omega: &Value) -> Result<Value, Error> {
+        self.monad(function, omega).map_err(|error| error.at(function.span()))
+    }
+
+    // Apply the function described by `function` to a left and a right
+    // argument, locating errors at the function
+    fn apply_dyad(&mut self, alpha: &Value, function: &Node, omega: &Value) -> Result<Value, Error> {
+        self.dyad(alpha, function, omega).map_err(|error| error.at(function.span()))
+    }
+
+    // Functions bound to a name may come from another source than the one
+    // being run, so their errors are located at the name rather than inside.
+    fn monad(&mut self, function: &Node, omega: &Value) -> Result<Value, Error> {
+        match function {
+            Node::MonadicOperator { operator, child, .. } => {
+                let child = child.as_ref().unwrap();
+                match operator {
+                    Token::MonadicOperator('⍨') => self.apply_dyad(omega, child, omega),
+                    Token::MonadicOperator(c @ ('/' | '⌿')) => {
+                        if omega.rank() == 0 {
+                            return Ok(omega.clone());
+                        }
+                        let axis = if *c == '⌿' { 0 } else { omega.rank() - 1 };
+                        let mut shape = omega.shape();
+                        let mut values: Vec<Value> = Vec::new();
+                        for vector in split_axis(&shape, &omega.ravel(), axis) {
+                            values.push(self.reduce(child, &vector)?);
+                        }
+                        shape.remove(axis);
+                        Ok(Value::new_array(shape, values))
                     }
-                    Token::MonadicOperator('/') => {
-                        if let Node::Array { values } = omega {
-                            if values.len() <= 1 {return Err("trying to reduce on a single element array".to_string());}
-                            let mut value = values[0].clone();
-                            for i in 1..values.len(){
-                                value = value_to_node(&self.visit_dyad(&value, child.as_ref().unwrap(), &values[i])?);
-                            }
-                            Ok(node_to_value(&value))
+                    Token::MonadicOperator(c @ ('\\' | '⍀')) => {
+                        if omega.rank() == 0 {
+                            return Ok(omega.clone());
                         }
-                        else {unreachable!()}
-                    },
+                        let axis = if *c == '⍀' { 0 } else { omega.rank() - 1 };
+                        let shape = omega.shape();
+                        let mut vectors: Vec<Vec<Value>> = Vec::new();
+                        for vector in split_axis(&shape, &omega.ravel(), axis) {
+                            vectors.push(self.scan(child, &vector)?);
+                        }
+                        Ok(join_axis(shape, axis, vectors))
+                    }
                     Token::MonadicOperator('¨') => {
-                        if let Node::Array { values } = omega {
-                            let mut vector: Vec<Value> = Vec::new();
-                            for value in values {
-                                vector.push(self.visit_monad(child.as_ref().unwrap(), value)?)
+                        let mut values: Vec<Value> = Vec::new();
+                        for value in omega.ravel() {
+                            values.push(self.apply_monad(child, &value)?);
+                        }
+                        Ok(Value::new_array(omega.shape(), values))
+                    }
+                    _ => Err(Error::nonce("operator not implemented")),
+                }
+            }
+            Node::DyadicOperator { .. } => Err(Error::syntax("missing left argument")),
+            Node::Axis { function, axis, .. } => match function.as_ref() {
+                Node::F { token: Token::Function(',' | '⍪'), .. } => {
+                    let axis = self.axis(axis)?;
+                    match axis.fract() == 0. {
+                        true => Err(Error::nonce("merging axes is not implemented")),
+                        false => ravel_new_axis(omega, axis.ceil() as usize),
+                    }
+                }
+                Node::F { token: Token::Function('⌽' | '⊖'), .. } => match self.axis(axis)? {
+                    axis if axis.fract() == 0. => reverse_axis(omega, axis as usize),
+                    _ => Err(Error::axis("")),
+                },
+                _ => Err(Error::axis("")),
+            },
+            _ => match self.visit_node(function, Some(1))? {
+                Value::MonadicFunction(f) => (f.function)(omega),
+                Value::DyadicFunction(f) => self.monad(&f.node, omega).map_err(Error::without_span),
+                Value::DerivedFunction(f) => self.monad(&f.node, omega).map_err(Error::without_span),
+                Value::Dfn(dfn) => self.call_dfn(&dfn, None, omega),
+                _ => Err(Error::syntax("not a function")),
+            },
+        }
+    }
+
+    fn dyad(&mut self, alpha: &Value, function: &Node, omega: &Value) -> Result<Value, Error> {
+        match function {
+            Node::MonadicOperator { operator, child, .. } => {
+                let child = child.as_ref().unwrap();
+                match operator {
+                    Token::MonadicOperator('⍨') => self.apply_dyad(omega, child, alpha),
+                    Token::MonadicOperator(c @ ('/' | '⌿')) => {
+                        if alpha.rank() > 1 {
+                            return Err(Error::rank(""));
+                        }
+                        let window = match alpha.ravel().as_slice() {
+                            [Value::Number(n)] => n.as_int().ok_or(Error::domain(""))?,
+                            [_] => return Err(Error::domain("")),
+                            _ => return Err(Error::length("")),
+                        };
+                        let mut shape = match omega.rank() {
+                            0 => vec![1],
+                            _ => omega.shape(),
+                        };
+                        let axis = if *c == '⌿' { 0 } else { shape.len() - 1 };
+                        let size = window.unsigned_abs() as usize;
+                        let windows = (shape[axis] + 1).checked_sub(size).ok_or(Error::length(""))?;
+                        let mut vectors: Vec<Vec<Value>> = Vec::new();
+                        for vector in split_axis(&shape, &omega.ravel(), axis) {
+                            let mut reductions: Vec<Value> = Vec::with_capacity(windows);
+                            for start in 0..windows {
+                                let mut items = vector[start..start + size].to_vec();
+                                // A negative window size reduces each window reversed
+                                if window < 0 {
+                                    items.reverse();
+                                }
+                                reductions.push(self.reduce(child, &items)?);
                             }
-                            Ok(Value::Array(vector))
+                            vectors.push(reductions);
                         }
-                        else {unreachable!()}
+                        shape[axis] = windows;
+                        Ok(join_axis(shape, axis, vectors))
                     }
-                    _ => {
-                        Err("Operator not implemented".to_string())
+                    Token::MonadicOperator('¨') => {
+                        let shape = paired_shape(alpha, omega)?;
+                        let (alphas, omegas) = (alpha.ravel(), omega.ravel());
+                        let mut values: Vec<Value> = Vec::new();
+                        for i in 0..shape.iter().product() {
+                            let a = &alphas[i % alphas.len()];
+                            let w = &omegas[i % omegas.len()];
+                            values.push(self.apply_dyad(a, child, w)?);
+                        }
+                        Ok(Value::new_array(shape, values))
                     }
+                    _ => Err(Error::nonce("operator not implemented")),
                 }
             }
-            _ => {
-                let function: Value = self.visit_node(operator, Some(1))?;
-                if let Value::MonadicFunction(f) = function {
-                    let omega: Value = self.visit_node(right, None)?;
-                    return Ok((f.function)(&omega)?);
+            Node::Axis { function, axis, .. } => match function.as_ref() {
+                Node::F { token: Token::Function(',' | '⍪'), .. } => {
+                    let axis = self.axis(axis)?;
+                    match axis.fract() == 0. {
+                        true => catenate_axis(alpha, omega, axis as usize),
+                        false => laminate(alpha, omega, axis.ceil() as usize),
+                    }
+                }
+                Node::F { token: Token::Function('⌽' | '⊖'), .. } => match self.axis(axis)? {
+                    axis if axis.fract() == 0. => rotate_axis(alpha, omega, axis as usize),
+                    _ => Err(Error::axis("")),
+                },
+                _ => Err(Error::axis("")),
+            },
+            Node::DyadicOperator { operator: Token::DyadicOperator('.'), left, right, .. } => {
+                match left.as_ref() {
+                    Node::F { token: Token::Function('∘'), .. } => self.outer_product(alpha, right, omega),
+                    _ => self.inner_product(alpha, left, right, omega),
                 }
-                Err("Problem".to_string())
             }
+            _ => self.dyad_function(alpha, function, omega),
         }
     }
 
-    fn visit_dyad(&mut self, alpha: &Node, operator: &Node, omega: &Node) -> Result<Value, String> {
-        let function: Value = self.visit_node(operator, Some(2))?;
-        if let Value::DyadicFunction(f) = function {
-            let alpha_v: Value = self.visit_node(alpha, None)?;
-            let omega_v: Value = self.visit_node(omega, None)?;
-            return Ok((f.function)(&alpha_v, &omega_v)?);
+    // Evaluate the axis given to a function, counted from 0. A fractional
+    // axis stands for a new axis between two existing ones.
+    fn axis(&mut self, axis: &Node) -> Result<f64, Error> {
+        let value = self.visit_node(axis, None)?;
+        let axis = match value.ravel().as_slice() {
+            [Value::Number(n)] if value.rank() <= 1 && !n.is_complex() => n.as_f64(),
+            _ => return Err(Error::axis("")),
+        };
+        let axis = axis - self.index_origin as f64;
+        match axis > -1. {
+            true => Ok(axis),
+            false => Err(Error::axis("")),
         }
-        Err("Problem".to_string())
     }
 
-    fn visit_f(&mut self, token: &Token, valence: i32) -> Result<Value, String> {
-        match token {
-            Token::Function('+') => match valence {
-                1 => {
-                    return Ok(Value::MonadicFunction(MonadicFunctionHolder {
-                        function: Rc::new(conjugate),
-                        node: Node::F {
-                            token: token.clone(),
-                        },
-                    }));
-                }
-                2 => {
-                    return Ok(Value::DyadicFunction(DyadicFunctionHolder {
-                        function: Rc::new(plus),
-                        node: Node::F {
-                            token: token.clone(),
-                        },
-                    }));
-                }
-                _ => return Err("Bad valence".to_string()),
-            },
-            Token::Function('×') => match valence {
-                1 => {
-                    return Ok(Value::MonadicFunction(MonadicFunctionHolder {
-                        function: Rc::new(direction),
-                        node: Node::F {
-                            token: token.clone(),
-                        },
-                    }));
-                }
-                2 => {
-                    return Ok(Value::DyadicFunction(DyadicFunctionHolder {
-                        function: Rc::new(times),
-                        node: Node::F {
-                            token: token.clone(),
-                        },
-                    }));
-                }
-                _ => return Err("Bad valence".to_string()),
-            },
-            Token::Function('⌈') => match valence {
-                1 => {
-                    return Ok(Value::MonadicFunction(MonadicFunctionHolder {
-                        function: Rc::new(ceiling),
-                        node: Node::F {
-                            token: token.clone(),
-                        },
-                    }));
+    // The outer product `⍺ ∘.f ⍵`, applying `f` between every item of `⍺`
+    // and every item of `⍵`.
+    fn outer_product(&mut self, alpha: &Value, function: &Node, omega: &Value) -> Result<Value, Error> {
+        let mut shape = alpha.shape();
+        shape.extend(omega.shape());
+        let mut values: Vec<Value> = Vec::with_capacity(length(&shape)?);
+        for a in alpha.ravel() {
+            for w in omega.ravel() {
+                values.push(self.apply_dyad(&a, function, &w)?);
+            }
+        }
+        Ok(Value::new_array(shape, values))
+    }
+
+    // The inner product `⍺ f.g ⍵`, applying `g` between each vector along the
+    // last axis of `⍺` and each vector along the first axis of `⍵`, and
+    // reducing each result with `f`.
+    fn inner_product(&mut self, alpha: &Value, f: &Node, g: &Node, omega: &Value) -> Result<Value, Error> {
+        let (alpha_shape, omega_shape) = (alpha.shape(), omega.shape());
+        let (alpha_values, omega_values) = (alpha.ravel(), omega.ravel());
+        let (frame, length1) = match alpha_shape.split_last() {
+            Some((&length, frame)) => (frame.to_vec(), length),
+            None => (Vec::new(), 1),
+        };
+        let (length2, cells) = match omega_shape.split_first() {
+            Some((&length, cells)) => (length, cells.to_vec()),
+            None => (1, Vec::new()),
+        };
+        let rows: usize = frame.iter().product();
+        let columns: usize = cells.iter().product();
+        let mut shape = frame;
+        shape.extend(cells);
+        let mut values: Vec<Value> = Vec::with_capacity(length(&shape)?);
+        for row in 0..rows {
+            let left = Value::vector(alpha_values[row * length1..(row + 1) * length1].to_vec());
+            for column in 0..columns {
+                let right = Value::vector(
+                    (0..length2).map(|k| omega_values[k * columns + column].clone()).collect(),
+                );
+                let products = self.apply_dyad(&left, g, &right)?;
+                values.push(self.reduce(f, &products.ravel())?);
+            }
+        }
+        Ok(Value::new_array(shape, values))
+    }
+
+    // The reductions of every prefix of `values` by `function`. Associative
+    // primitives extend the previous prefix instead of reducing from scratch.
+    fn scan(&mut self, function: &Node, values: &[Value]) -> Result<Vec<Value>, Error> {
+        let associative = matches!(
+            function,
+            Node::F { token: Token::Function('+' | '×' | '⌈' | '⌊' | '∧' | '∨'), .. }
+        );
+        let mut result: Vec<Value> = Vec::with_capacity(values.len());
+        for (k, value) in values.iter().enumerate() {
+            let prefix = match (associative, result.last()) {
+                (true, Some(previous)) => self.apply_dyad(previous, function, value)?,
+                _ => self.reduce(function, &values[..=k])?,
+            };
+            result.push(prefix);
+        }
+        Ok(result)
+    }
+
+    // Insert `function` between the items of `values`, grouping from the
+    // right as APL evaluates. Reducing no items gives the identity element of
+    // the function, if it has one.
+    fn reduce(&mut self, function: &Node, values: &[Value]) -> Result<Value, Error> {
+        let (last, rest) = match (values.split_last(), function) {
+            (Some(split), _) => split,
+            (None, Node::F { token: Token::Function(c), .. }) => {
+                return identity(*c).ok_or(Error::domain("function has no identity element"));
+            }
+            (None, _) => return Err(Error::domain("function has no identity element")),
+        };
+        let mut value = last.clone();
+        for v in rest.iter().rev() {
+            value = self.apply_dyad(v, function, &value)?;
+        }
+        Ok(value)
+    }
+
+    fn dyad_function(&mut self, alpha: &Value, function: &Node, omega: &Value) -> Result<Value, Error> {
+        match self.visit_node(function, Some(2))? {
+            Value::DyadicFunction(f) => (f.function)(alpha, omega),
+            Value::MonadicFunction(f) => self.dyad(alpha, &f.node, omega).map_err(Error::without_span),
+            Value::DerivedFunction(f) => self.dyad(alpha, &f.node, omega).map_err(Error::without_span),
+            Value::Dfn(dfn) => self.call_dfn(&dfn, Some(alpha), omega),
+            _ => Err(Error::syntax("not a function")),
+        }
+    }
+
+    // Run the body of a dfn in a new scope where ⍺, ⍵ and ∇ are bound, chained
+    // to the scope the dfn was defined in. A dfn that outlives the call it was
+    // defined in only sees the globals.
+    fn call_dfn(&mut self, dfn: &DfnHolder, alpha: Option<&Value>, omega: &Value) -> Result<Value, Error> {
+        let parent = dfn.scope.upgrade().unwrap_or_else(|| self.globals.clone());
+        let scope = Scope::new(Some(parent));
+        {
+            let mut variables = scope.variables.borrow_mut();
+            variables.insert("⍵".to_string(), omega.clone());
+            if let Some(alpha) = alpha {
+                variables.insert("⍺".to_string(), alpha.clone());
+            }
+            variables.insert("∇".to_string(), Value::Dfn(dfn.clone()));
+        }
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::limit("maximum recursion depth exceeded"));
+        }
+        self.depth += 1;
+        let caller = std::mem::replace(&mut self.scope, scope);
+        let result = self.run_dfn_body(&dfn.node);
+        self.scope = caller;
+        self.depth -= 1;
+        result.map_err(Error::without_span)
+    }
+
+    fn run_dfn_body(&mut self, dfn: &Node) -> Result<Value, Error> {
+        let body = match dfn {
+            Node::Dfn { body, .. } => body,
+            _ => return Err(Error::syntax("not a dfn")),
+        };
+        for statement in body {
+            match statement {
+                Node::Guard { condition, result, .. } => {
+                    match self.visit_node(condition, None)? {
+                        Value::Number(Number::Bool(true)) => return self.visit_node(result, None),
+                        Value::Number(Number::Bool(false)) => {}
+                        _ => return Err(Error::domain("guard must be 0 or 1")),
+                    }
                 }
-                2 => {
-                    return Ok(Value::DyadicFunction(DyadicFunctionHolder {
-                        function: Rc::new(maximum),
-                        node: Node::F {
-                            token: token.clone(),
-                        },
-                    }));
+                Node::Assignement { .. } => {
+                    self.visit_node(statement, None)?;
                 }
-                _ => return Err("Bad valence".to_string()),
+                // The first expression that is not an assignment is the result
+                _ => return self.visit_node(statement, None),
             }
-            Token::Function('⌊') => match valence {
-                1 => {
-                    return Ok(Value::MonadicFunction(MonadicFunctionHolder {
-                        function: Rc::new(floor),
-                        node: Node::F {
-                            token: token.clone(),
-                        },
-                    }));
-                }
-                2 => {
-                    return Ok(Value::DyadicFunction(DyadicFunctionHolder {
-                        function: Rc::new(minimum),
-                        node: Node::F {
-                            token: token.clone(),
-                        },
-                    }));
+        }
+        Ok(Value::None)
+    }
+}
+
+// The monadic form of a primitive that only has a dyadic one here.
+fn nonce(_omega: &Value) -> Result<Value, Error> {
+    Err(Error::nonce("monadic form is not implemented"))
+}
+
+// The dyadic form of a primitive that only has a monadic one here.
+fn nonce_dyad(_alpha: &Value, _omega: &Value) -> Result<Value, Error> {
+    Err(Error::nonce("dyadic form is not implemented"))
+}
+
+// The address of a local variable, which tells how deep the stack is.
+#[inline(never)]
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+fn primitive<M, D>(token: &Token, span: Span, valence: i32, monadic: M, dyadic: D) -> Result<Value, Error>
+where
+    M: Fn(&Value) -> Result<Value, Error> + 'static,
+    D: Fn(&Value, &Value) -> Result<Value, Error> + 'static,
+{
+    let node = Node::F {
+        token: token.clone(),
+        span,
+    };
+    match valence {
+        1 => Ok(Value::MonadicFunction(MonadicFunctionHolder {
+            function: Rc::new(monadic),
+            node,
+        })),
+        2 => Ok(Value::DyadicFunction(DyadicFunctionHolder {
+            function: Rc::new(dyadic),
+            node,
+        })),
+        _ => Err(Error::syntax("bad valence")),
+    }
+}
+
+impl Visitor<Result<Value, Error>> for Interpreter {
+    fn visit_scalar(&mut self, token: &Token) -> Result<Value, Error> {
+        match token {
+            Token::Number(value) => Ok(Value::Number(*value)),
+            // A single character between quotes is a scalar, anything else a vector
+            Token::String(string) => match string.chars().count() {
+                1 => Ok(Value::Char(string.chars().next().unwrap())),
+                _ => Ok(Value::vector(string.chars().map(Value::Char).collect()).with_fill(Value::Char(' '))),
+            },
+            _ => Err(Error::syntax("scalar must be a number or a string")),
+        }
+    }
+
+    fn visit_array(&mut self, values: &[Node]) -> Result<Value, Error> {
+        let mut array: Vec<Value> = Vec::new();
+        for value in values {
+            match self.visit_node(value, None)? {
+                item if item.is_function() => {
+                    return Err(Error::syntax("a function cannot be an array item").at(value.span()))
                 }
-                _ => return Err("Bad valence".to_string()),
+                item => array.push(item),
             }
-            _ => {
-                println!("token: {:?}", token);
-                return Err("Parsing error".to_string());
+        }
+        Ok(Value::vector(array))
+    }
+
+    // Evaluate a function that have only one argument
+    fn visit_monad(&mut self, operator: &Node, right: &Node) -> Result<Value, Error> {
+        let omega = self.visit_node(right, None)?;
+        self.apply_monad(operator, &omega)
+    }
+
+    fn visit_dyad(&mut self, alpha: &Node, operator: &Node, omega: &Node) -> Result<Value, Error> {
+        let omega_v: Value = self.visit_node(omega, None)?;
+        let alpha_v: Value = self.visit_node(alpha, None)?;
+        self.apply_dyad(&alpha_v, operator, &omega_v)
+    }
+
+    fn visit_f(&mut self, token: &Token, valence: i32, span: Span) -> Result<Value, Error> {
+        match token {
+            Token::Function('+') => primitive(token, span, valence, conjugate, plus),
+            Token::Function('-') => primitive(token, span, valence, negate, minus),
+            Token::Function('×') => primitive(token, span, valence, direction, times),
+            Token::Function('÷') => primitive(token, span, valence, reciprocal, divide),
+            Token::Function('|') => primitive(token, span, valence, magnitude, residue),
+            Token::Function('*') => primitive(token, span, valence, exponential, power),
+            Token::Function('⍟') => primitive(token, span, valence, natural_logarithm, logarithm),
+            Token::Function('!') => primitive(token, span, valence, factorial, binomial),
+            Token::Function('○') => primitive(token, span, valence, pi_times, circular),
+            Token::Function('=') => primitive(token, span, valence, nonce, equal),
+            Token::Function('≠') => primitive(token, span, valence, nonce, not_equal),
+            Token::Function('<') => primitive(token, span, valence, nonce, less),
+            Token::Function('≤') => primitive(token, span, valence, nonce, less_or_equal),
+            Token::Function('≥') => primitive(token, span, valence, nonce, greater_or_equal),
+            Token::Function('>') => primitive(token, span, valence, nonce, greater),
+            Token::Function('∧') => primitive(token, span, valence, nonce, and),
+            Token::Function('∨') => primitive(token, span, valence, nonce, or),
+            Token::Function('⍲') => primitive(token, span, valence, nonce, nand),
+            Token::Function('⍱') => primitive(token, span, valence, nonce, nor),
+            Token::Function('~') => primitive(token, span, valence, not, without),
+            Token::Function('/') => primitive(token, span, valence, nonce, replicate),
+            Token::Function('⌿') => primitive(token, span, valence, nonce, replicate_first),
+            Token::Function('\\') => primitive(token, span, valence, nonce, expand),
+            Token::Function('⍀') => primitive(token, span, valence, nonce, expand_first),
+            Token::Function(',') => primitive(token, span, valence, ravel, catenate),
+            Token::Function('⍪') => primitive(token, span, valence, table, catenate_first),
+            Token::Function('∊') => primitive(token, span, valence, enlist, nonce_dyad),
+            Token::Function('↑') => primitive(token, span, valence, nonce, take),
+            Token::Function('↓') => primitive(token, span, valence, nonce, drop),
+            Token::Function('⌽') => primitive(token, span, valence, reverse, rotate),
+            Token::Function('⊖') => primitive(token, span, valence, reverse_first, rotate_first),
+            Token::Function('⌈') => primitive(token, span, valence, ceiling, maximum),
+            Token::Function('⌊') => primitive(token, span, valence, floor, minimum),
+            Token::Function('⍴') => primitive(token, span, valence, shape, reshape),
+            Token::Function('⍳') => {
+                let origin = self.index_origin;
+                primitive(
+                    token,
+                    span,
+                    valence,
+                    move |omega| index_generator(omega, origin),
+                    move |alpha, omega| index_of(alpha, omega, origin),
+                )
             }
+            Token::Function(c) => Err(Error::nonce(&format!("{} is not implemented", c))),
+            _ => Err(Error::syntax("expected a function")),
         }
     }
 
-    fn visit_stmt(&mut self, children: &Vec<Node>) -> Result<Value, String> {
+    // The value of a list of statements is the value of the last one
+    fn visit_stmt(&mut self, children: &[Node]) -> Result<Value, Error> {
+        let mut value = Value::None;
         for statement in children {
-            let v = self.visit_node(statement, None)?;
-            println!("{:?}", v)
+            value = self.visit_node(statement, None)?;
         }
-        Ok(Value::None)
+        Ok(value)
     }
 
-}
+    fn visit_assignment(&mut self, varname: &Node, value: &Node) -> Result<Value, Error> {
+        let name = match varname {
+            Node::Var { token: Token::Id(name) | Token::FunctionId(name), .. } => name,
+            _ => return Err(Error::syntax("can only assign to a name")),
+        };
+        // `⍺←` only gives a default left argument to a dfn called monadically
+        if name == "⍺" {
+            if let Some(alpha) = self.scope.get(name) {
+                return Ok(alpha);
+            }
+        }
+        let value = self.visit_node(value, None)?;
+        self.assign(name, value.clone())?;
+        Ok(value)
+    }
+
+    fn visit_var(&mut self, token: &Token) -> Result<Value, Error> {
+        match token {
+            Token::Id(name) | Token::FunctionId(name) => self.lookup(name),
+            _ => Err(Error::syntax("expected a name")),
+        }
+    }
 
+    fn visit_guard(&mut self, _condition: &Node, _result: &Node) -> Result<Value, Error> {
+        Err(Error::syntax("guard outside of a dfn"))
+    }
 
+    fn visit_dfn(&mut self, body: &[Node], span: Span) -> Result<Value, Error> {
+        Ok(Value::Dfn(DfnHolder {
+            node: Node::Dfn {
+                body: body.to_vec(),
+                span,
+            },
+            scope: Rc::downgrade(&self.scope),
+        }))
+    }
 
-fn value_to_node(value: &Value) -> Node{
-    match value{
-        Value::Array(values) => {return Node::Array { values: values.iter().map(|el| return value_to_node(el)).collect() }},
-        Value::Number(v) => {return Node::Scalar { token: Token::Number(*v) }}
-        _ => unreachable!()
+    fn visit_dyadic_operator(&mut self, operator: &Token, left: &Node, right: &Node, span: Span) -> Result<Value, Error> {
+        Ok(Value::DerivedFunction(DerivedFunctionHolder {
+            node: Node::DyadicOperator {
+                operator: operator.clone(),
+                left: Box::new(left.clone()),
+                right: Box::new(right.clone()),
+                span,
+            },
+        }))
     }
+
+    fn visit_axis(&mut self, function: &Node, axis: &Node, span: Span) -> Result<Value, Error> {
+        Ok(Value::DerivedFunction(DerivedFunctionHolder {
+            node: Node::Axis {
+                function: Box::new(function.clone()),
+                axis: Box::new(axis.clone()),
+                span,
+            },
+        }))
+    }
+
+    fn visit_operator(&mut self, operator: &Token, child: &Node, span: Span) -> Result<Value, Error> {
+        Ok(Value::DerivedFunction(DerivedFunctionHolder {
+            node: Node::MonadicOperator {
+                operator: operator.clone(),
+                child: Some(Box::new(child.clone())),
+                span,
+            },
+        }))
+    }
+
 }
 
-fn node_to_value(node: &Node) -> Value{
-    match node{
-        Node::Array { values } => {return Value::Array(values.iter().map(|el| return node_to_value(el)).collect())}
-        Node::Scalar { token } => {
-            match token {
-                Token::Number(x) => {return Value::Number(*x);}
-                _ => todo!()
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::repl::{evaluate, execute};
+
+    // Run `source` on a thread with the interpreter's stack size.
+    fn run(source: &'static str) -> Result<(), Error> {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || {
+                let mut interpreter = Interpreter::new();
+                interpreter.set_stack_size(STACK_SIZE);
+                execute(&mut interpreter, source, |_| {})
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+
+    #[test]
+    fn dfns_are_lexically_scoped() {
+        assert_eq!(evaluate("x←1 ⋄ f←{x} ⋄ g←{x←2 ⋄ f ⍵} ⋄ g 0"), Ok("1".to_string()));
+        assert_eq!(evaluate("h←{y←⍵ ⋄ k←{y+⍵} ⋄ k 1} ⋄ h 10"), Ok("11".to_string()));
+        let error = evaluate("z←{q←⍵} ⋄ z 4 ⋄ q").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Value);
+    }
+
+    #[test]
+    fn alpha_is_only_bound_in_its_own_dfn() {
+        let error = evaluate("inner←{⍺} ⋄ outer←{inner ⍵} ⋄ 5 outer 1").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Value);
+        assert_eq!(evaluate("m←{⍺←3 ⋄ ⍺+⍵} ⋄ m 1 ⋄ 1 m 1"), Ok("4\n2".to_string()));
+    }
+
+    #[test]
+    fn parenthesised_functions_are_functions() {
+        assert_eq!(evaluate("1 (+) 2"), Ok("3".to_string()));
+        assert_eq!(evaluate("⍴⍴(+/)1 2"), Ok("0".to_string()));
+        assert_eq!(evaluate("a←(+/) ⋄ a 1 2"), Ok("3".to_string()));
+        assert_eq!(evaluate("2 (×/) 3 4"), Ok("12".to_string()));
+        assert_eq!(evaluate("⍴(1 2)(3 4)"), Ok("2".to_string()));
+        assert_eq!(evaluate("((((1))))"), Ok("1".to_string()));
+    }
+
+    #[test]
+    fn recursion_up_to_the_limit_succeeds() {
+        assert_eq!(run("g←{0=⍵:0 ⋄ 1+∇ ⍵-1} ⋄ g 999"), Ok(()));
+        assert_eq!(run("g←{0=⍵:0 ⋄ 1+∇¨⍵-1} ⋄ g 999"), Ok(()));
+    }
+
+    #[test]
+    fn products_too_large_are_a_limit_error() {
+        let error = run("(⍳10000)∘.+⍳10000").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Limit);
+        assert_eq!(run("⍴(⍳100)∘.+⍳100"), Ok(()));
+        let error = run("(10000 1⍴1)+.×1 10000⍴1").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Limit);
+    }
+
+    #[test]
+    fn recursion_is_limited_by_the_default_stack() {
+        let source = "g←{1+∇ ⍵} ⋄ g 1";
+        let thread = std::thread::Builder::new().stack_size(DEFAULT_STACK_SIZE);
+        let result = thread.spawn(|| execute(&mut Interpreter::new(), source, |_| {}));
+        let error = result.unwrap().join().unwrap().unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Limit);
+    }
+
+    #[test]
+    fn runaway_recursion_is_a_limit_error() {
+        for source in ["g←{∇ ⍵} ⋄ g 1", "g←{∇¨,⍵} ⋄ g 1", "g←{∘.{∇ ⍵}⍨⍵} ⋄ g 1"] {
+            assert_eq!(run(source).map_err(|error| error.kind), Err(ErrorKind::Limit));
         }
-        _ => unreachable!()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn reductions_fold_from_the_right() {
+        assert_eq!(evaluate("-/1 2 3"), Ok("2".to_string()));
+        assert_eq!(evaluate("÷/2 4 8"), Ok("4".to_string()));
+        assert_eq!(evaluate("+/5"), Ok("5".to_string()));
+    }
+
+    #[test]
+    fn reductions_of_empty_vectors_are_identities() {
+        assert_eq!(evaluate("+/⍬"), Ok("0".to_string()));
+        assert_eq!(evaluate("×/⍬"), Ok("1".to_string()));
+        assert_eq!(evaluate("(⌈/⍬)=-⌊/⍬"), Ok("1".to_string()));
+        assert_eq!(evaluate("(⌈/⍬)<¯1E300"), Ok("1".to_string()));
+        let error = evaluate("{⍺+⍵}/⍬").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Domain);
+    }
+
+    #[test]
+    fn reductions_of_matrices_follow_their_axis() {
+        assert_eq!(evaluate("+/2 3⍴⍳6"), Ok("6 15".to_string()));
+        assert_eq!(evaluate("+⌿2 3⍴⍳6"), Ok("5 7 9".to_string()));
+        assert_eq!(evaluate("-⌿3 2⍴⍳6"), Ok("3 4".to_string()));
+    }
+
+    #[test]
+    fn windowed_reductions() {
+        assert_eq!(evaluate("2+/⍳5"), Ok("3 5 7 9".to_string()));
+        assert_eq!(evaluate("2-/1 4 9 16"), Ok("¯3 ¯5 ¯7".to_string()));
+        assert_eq!(evaluate("3+/⍳3"), Ok("6".to_string()));
+        assert_eq!(evaluate("1+/⍳3"), Ok("1 2 3".to_string()));
+        assert_eq!(evaluate("2+/2 3⍴⍳6"), Ok("3  5\n9 11".to_string()));
+        assert_eq!(evaluate("2+⌿3 2⍴⍳6"), Ok("4  6\n8 10".to_string()));
+    }
+
+    #[test]
+    fn negative_windows_reverse_each_window() {
+        assert_eq!(evaluate("¯2-/1 4 9 16"), Ok("3 5 7".to_string()));
+        assert_eq!(evaluate("⍴¯4+/⍳3"), Ok("0".to_string()));
+    }
+
+    #[test]
+    fn empty_windows_give_identities() {
+        assert_eq!(evaluate("0+/1 2 3"), Ok("0 0 0 0".to_string()));
+        assert_eq!(evaluate("0×/⍳2"), Ok("1 1 1".to_string()));
+    }
+
+    #[test]
+    fn windows_longer_than_the_vector() {
+        assert_eq!(evaluate("⍴4+/⍳3"), Ok("0".to_string()));
+        assert_eq!(evaluate("5+/⍳3").unwrap_err().kind, ErrorKind::Length);
+    }
+}
@@ -1,89 +1,237 @@
-use crate::token::{Token};
-use std::iter::Peekable;
+use crate::error::Error;
+use crate::complex::Complex;
+use crate::number::Number;
+use crate::token::{Span, SpannedToken, Token};
+use std::str::Chars;
 
-pub fn scan_tokens(src: &String) -> Result<Vec<Token>, String>{
-    let mut tokens: Vec<Token> = Vec::new();
-    let mut it =  src.chars().peekable();
-    tokens.push(Token::Eof);
-    while let Some(&c) = it.peek(){
-        match c{
-            '0'..='9' | '¯' => {
-                it.next();
-                let num = number(c, &mut it);
-                tokens.push(Token::Number(num));
+pub fn scan_tokens(src: &str) -> Result<Vec<SpannedToken>, Error>{
+    let mut tokens: Vec<SpannedToken> = vec![SpannedToken {
+        token: Token::Eof,
+        span: Span { start: src.len(), end: src.len() },
+    }];
+    let mut it = src.chars();
+    // The byte offset of the next character
+    let position = |it: &Chars| src.len() - it.as_str().len();
+    while let Some(c) = peek(&it){
+        let start = position(&it);
+        let token = match c{
+            // A number may start with its decimal point
+            '0'..='9' | '¯' | '.' if c != '.' || it.clone().nth(1).is_some_and(|c| c.is_ascii_digit()) => {
+                it.next();
+                let num = number(c, &mut it).map_err(|error| error.at(Span { start, end: position(&it) }))?;
+                Token::Number(num)
             },
-            'a'..='z' | 'A'..='Z' => {
+            'a'..='z' | 'A'..='Z' | '⎕' => {
                 it.next();
-                let id = get_id(c, &mut it);
-                tokens.push(Token::Id(id));
+                Token::Id(get_id(c, &mut it))
             }
-            '+' | '-' | '×' | '÷' | '⌈' | '⌊' => {
-                tokens.push(Token::Function(c));
+            '+' | '-' | '×' | '÷' | '|' | '*' | '⍟' | '!' | '○' | '∘'
+            | '=' | '≠' | '<' | '≤' | '≥' | '>' | '∧' | '∨' | '⍲' | '⍱' | '~' | ',' | '⍪' | '∊'
+            | '↑' | '↓' | '⌽' | '⊖'
+            | '⌈' | '⌊' | '⍴' | '⍳' => {
                 it.next();
+                Token::Function(c)
             },
             '⍨' | '¨' => {
-                tokens.push(Token::MonadicOperator(c));
                 it.next();
+                Token::MonadicOperator(c)
+            }
+            '.' => {
+                it.next();
+                Token::DyadicOperator(c)
+            }
+            '\'' => {
+                it.next();
+                let string = string(&mut it).map_err(|error| error.at(Span { start, end: position(&it) }))?;
+                Token::String(string)
+            },
+            '⍬' => {
+                it.next();
+                Token::Zilde
+            },
+            '⍺' | '⍵' | '∇' => {
+                it.next();
+                Token::Id(c.to_string())
             }
             '(' | ')' => {
-                tokens.push(Token::Paren(c));
                 it.next();
+                Token::Paren(c)
+            },
+            '{' | '}' => {
+                it.next();
+                Token::Brace(c)
+            },
+            '[' | ']' => {
+                it.next();
+                Token::Bracket(c)
+            },
+            ':' => {
+                it.next();
+                Token::Guard
             },
             '←' => {
-                tokens.push(Token::Assign);
                 it.next();
+                Token::Assign
             },
-            '⋄' => {
-                tokens.push(Token::Diamond);
+            '⋄' | '\n' => {
                 it.next();
+                Token::Diamond
             },
             '/' | '⌿' | '\\' | '⍀' => {
-                if let Some(Token::Function(_)) = tokens.last(){
-                    tokens.push(Token::MonadicOperator(c));
+                it.next();
+                match tokens.last().map(|last| &last.token) {
+                    Some(Token::Function(_) | Token::Brace('}')) => Token::MonadicOperator(c),
+                    _ => Token::Function(c),
                 }
-                else{
-                    tokens.push(Token::Function(c));
+            },
+            '⍝' => {
+                // A comment runs until the end of the line
+                while peek(&it).is_some_and(|c| c != '\n') {
+                    it.next();
                 }
+                continue;
+            },
+            ' ' | '\t' | '\r' => {
                 it.next();
+                continue;
             },
-            ' ' => {it.next();},
             _ => {
-                return Err(format!("unexpected character {}", c));
+                let error = Error::syntax(&format!("unexpected character {}", c));
+                return Err(error.at(Span { start, end: start + c.len_utf8() }));
             }
-        }
+        };
+        tokens.push(SpannedToken {
+            token,
+            span: Span { start, end: position(&it) },
+        });
     }
     Ok(tokens)
 }
 
-fn number<T: Iterator<Item = char>>(mut c: char, iter: &mut Peekable<T>) -> f32{
-    let mut negative: f32 = 1.;
-    if c == '¯' {
-        negative = -1.;
-        c = iter.next().unwrap_or('a'); // We put a random letter that will fail the expect
+fn peek(it: &Chars) -> Option<char> {
+    it.clone().next()
+}
+
+// Read a number whose first character has been consumed, which is complex
+// when written as a real and an imaginary part separated by `J`.
+fn number(c: char, iter: &mut Chars) -> Result<Number, Error>{
+    let real = real_number(c, iter)?;
+    if !matches!(peek(iter), Some('J' | 'j')) {
+        return Ok(real);
     }
-    let mut number = c.to_string().parse::<f32>().expect("The caller should have passed a digit.");
-    while let Some(Ok(digit)) = iter.peek().map(|c| c.to_string().parse::<f32>()) {
-        number = number * 10. + digit;
+    iter.next();
+    let imaginary = match iter.next() {
+        Some(c) if c.is_ascii_digit() || c == '¯' || c == '.' => real_number(c, iter)?,
+        _ => return Err(Error::syntax("malformed number")),
+    };
+    Ok(Number::complex(Complex::new(real.as_f64(), imaginary.as_f64())))
+}
+
+// Read a real number whose first character has been consumed: an optional
+// `¯`, digits with at most one decimal point, and an optional exponent
+// introduced by `E`. Integers are parsed exactly, and only become floats when
+// they do not fit in 64 bits.
+fn real_number(c: char, iter: &mut Chars) -> Result<Number, Error>{
+    let malformed = || Error::syntax("malformed number");
+    let mut literal = String::new();
+    literal.push(if c == '¯' { '-' } else { c });
+    while let Some(c) = peek(iter).filter(|c| c.is_ascii_digit() || *c == '.') {
+        literal.push(c);
         iter.next();
     }
-    if iter.peek() == Some(&'.'){
+    let point = literal.contains('.');
+    if literal.matches('.').count() > 1 || !literal.contains(|c: char| c.is_ascii_digit()) {
+        return Err(malformed());
+    }
+    let exponent = matches!(peek(iter), Some('E' | 'e'));
+    if exponent {
         iter.next();
-        let mut i: i32 = 1;
-        while let Some(Ok(digit)) = iter.peek().map(|c| c.to_string().parse::<f32>()) {
-            number = number + digit * f32::powf(10.,-i as f32);
+        literal.push('e');
+        if peek(iter) == Some('¯') {
+            iter.next();
+            literal.push('-');
+        }
+        let mut digits = 0;
+        while let Some(d) = peek(iter).filter(char::is_ascii_digit) {
+            digits += 1;
+            literal.push(d);
             iter.next();
-            i+=1;
+        }
+        if digits == 0 {
+            return Err(malformed());
+        }
+    }
+    if !point && !exponent {
+        if let Ok(n) = literal.parse::<i64>() {
+            return Ok(Number::int(n));
+        }
+    }
+    match literal.parse::<f64>() {
+        Ok(x) if x.is_finite() => Ok(Number::float(x)),
+        Ok(_) => Err(Error::domain("number is too large")),
+        Err(_) => Err(malformed()),
+    }
+}
+
+// Read a quoted string whose opening quote has been consumed. A quote inside
+// the string is written twice.
+fn string(iter: &mut Chars) -> Result<String, Error>{
+    let mut string = String::new();
+    loop {
+        match iter.next() {
+            Some('\'') => {
+                if peek(iter) != Some('\'') {
+                    return Ok(string);
+                }
+                iter.next();
+                string.push('\'');
+            }
+            Some(c) => string.push(c),
+            None => return Err(Error::syntax("unterminated string")),
         }
     }
-    number * negative
 }
 
-fn get_id<T: Iterator<Item = char>>(c: char, iter: &mut Peekable<T>) -> String{
+fn get_id(c: char, iter: &mut Chars) -> String{
     let mut id: String = c.to_string();
-    while let Some(c) = iter.peek() {
-        if !c.is_alphanumeric() {break;}
-        id.push(*c);
+    while let Some(c) = peek(iter) {
+        if !c.is_alphanumeric() && c != '_' {break;}
+        id.push(c);
         iter.next();
     }
     id
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn number(source: &str) -> Result<Number, ErrorKind> {
+        // The first token is the end of the input
+        let tokens = scan_tokens(source).map_err(|error| error.kind)?;
+        match tokens[1].token {
+            Token::Number(n) => Ok(n),
+            ref token => panic!("expected a number, got {:?}", token),
+        }
+    }
+
+    #[test]
+    fn literals_with_exponents_and_leading_points() {
+        assert_eq!(number("1E6"), Ok(Number::Int(1000000)));
+        assert_eq!(number("1.5e¯3"), Ok(Number::Float(0.0015)));
+        assert_eq!(number(".5"), Ok(Number::Float(0.5)));
+        assert_eq!(number("¯.25"), Ok(Number::Float(-0.25)));
+        assert_eq!(number("9223372036854775807"), Ok(Number::Int(i64::MAX)));
+        assert_eq!(number("1J¯2"), Ok(Number::Complex(Complex::new(1., -2.))));
+    }
+
+    #[test]
+    fn malformed_literals() {
+        assert_eq!(number("1..2"), Err(ErrorKind::Syntax));
+        assert_eq!(number("1e"), Err(ErrorKind::Syntax));
+        assert_eq!(number("1e¯"), Err(ErrorKind::Syntax));
+        assert_eq!(number("¯"), Err(ErrorKind::Syntax));
+        assert_eq!(number("1e400"), Err(ErrorKind::Domain));
+    }
+}
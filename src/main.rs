@@ -1,28 +1,33 @@
+mod error;
 mod token;
 mod lexer;
 mod parser;
 mod node;
 mod interpreter;
+mod complex;
+mod number;
 mod value;
 mod functions;
+mod format;
+mod repl;
+mod script;
 
 fn main(){
-    let code = "(1 2 3) (1 2 3) (3 4 5)".to_string();
-    // let code= "+/1 2 3 10".to_string();
-    // let code = "×⍨ 4 3 56".to_string();
-    // let code = "×⍨ 4".to_string();
-    // let code = "×¯3".to_string();
-    // let code = "1 3 4 0 9 ⌈ 1.2 2 3 4 5".to_string();
-    let mut tokens = lexer::scan_tokens(&code).unwrap();
-    tokens.reverse();
-    // println!("{:?}", tokens);
-    let ast: node::Node = parser::parse(&tokens).unwrap();
-    println!("{:?}", ast);
-    println!("\n\n> {}", code);
-    let mut interpreter: interpreter::Interpreter = interpreter::Interpreter::new();
-    let _v = interpreter.interpret(&ast);
+    // Dfns recurse on the native stack, which the main thread has too little of
+    let thread = std::thread::Builder::new()
+        .stack_size(interpreter::STACK_SIZE)
+        .spawn(run)
+        .expect("cannot start the interpreter");
+    std::process::exit(thread.join().unwrap_or(1));
+}
 
-    // let v = interpreter::interpret(&ast);
-    // println!("{:?}", v);
-
-}
\ No newline at end of file
+fn run() -> i32 {
+    let mut args = std::env::args().skip(1);
+    match args.next() {
+        Some(path) => script::run(&path, args.collect()),
+        None => {
+            repl::run();
+            0
+        }
+    }
+}
@@ -1,55 +1,187 @@
-use crate::token::Token;
+use crate::format::format_number;
+use crate::token::{Span, Token};
+use std::fmt;
 
+/// A node of the syntax tree, with the span of source it was parsed from.
 #[derive(Debug, PartialEq, Clone)]
 pub enum Node {
     Scalar {
         token: Token,
+        span: Span,
     },
 
     Array {
         values: Vec<Node>,
+        span: Span,
     },
 
     MonadicOperator {
         operator: Token,
         child: Option<Box<Node>>,
+        span: Span,
+    },
+    DyadicOperator {
+        operator: Token,
+        left: Box<Node>,
+        right: Box<Node>,
+        span: Span,
     },
     Dyad {
         left: Option<Box<Node>>,
         operator: Box<Node>,
         right: Option<Box<Node>>,
+        span: Span,
     },
     F {
         token: Token,
+        span: Span,
     },
     Monad {
         operator: Box<Node>,
         right: Option<Box<Node>>,
+        span: Span,
     },
     Assignement {
         varname: Box<Node>,
         value: Box<Node>,
+        span: Span,
     },
     Var {
         token: Token,
+        span: Span,
     },
     Statement {
         children: Vec<Node>,
+        span: Span,
+    },
+    Dfn {
+        body: Vec<Node>,
+        span: Span,
     },
+    Guard {
+        condition: Box<Node>,
+        result: Box<Node>,
+        span: Span,
+    },
+    /// A function applied along the axis given in brackets, as in `,[1]`.
+    Axis {
+        function: Box<Node>,
+        axis: Box<Node>,
+        span: Span,
+    },
+}
+
+
+impl Node {
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Scalar { span, .. }
+            | Node::Array { span, .. }
+            | Node::MonadicOperator { span, .. }
+            | Node::DyadicOperator { span, .. }
+            | Node::Dyad { span, .. }
+            | Node::F { span, .. }
+            | Node::Monad { span, .. }
+            | Node::Assignement { span, .. }
+            | Node::Var { span, .. }
+            | Node::Statement { span, .. }
+            | Node::Dfn { span, .. }
+            | Node::Guard { span, .. }
+            | Node::Axis { span, .. } => *span,
+        }
+    }
 }
 
+/// Nodes display as the APL source they were parsed from, spaced and
+/// parenthesised canonically, so that functions can be shown by their
+/// definition.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self {
+            Node::Scalar { token, .. } | Node::F { token, .. } | Node::Var { token, .. } => {
+                token_source(token)
+            }
+            Node::Array { values, .. } if values.is_empty() => "⍬".to_string(),
+            Node::Array { values, .. } => values
+                .iter()
+                .map(|value| match value {
+                    Node::Scalar { .. } | Node::Var { .. } => value.to_string(),
+                    _ => format!("({})", value),
+                })
+                .collect::<Vec<String>>()
+                .join(" "),
+            Node::Monad { operator, right, .. } => {
+                adjoin(&operator.to_string(), &right.as_ref().unwrap().to_string())
+            }
+            Node::Dyad { left, operator, right, .. } => {
+                let left = match left.as_ref().unwrap().as_ref() {
+                    left @ (Node::Monad { .. } | Node::Dyad { .. } | Node::Assignement { .. }) => {
+                        format!("({})", left)
+                    }
+                    left => left.to_string(),
+                };
+                let left = adjoin(&left, &operator.to_string());
+                adjoin(&left, &right.as_ref().unwrap().to_string())
+            }
+            Node::Assignement { varname, value, .. } => format!("{}←{}", varname, value),
+            Node::Statement { children, .. } => statements(children),
+            Node::Dfn { body, .. } => format!("{{{}}}", statements(body)),
+            Node::Guard { condition, result, .. } => format!("{}:{}", condition, result),
+            Node::MonadicOperator { operator, child, .. } => {
+                format!("{}{}", child.as_ref().unwrap(), token_source(operator))
+            }
+            Node::DyadicOperator { operator, left, right, .. } => {
+                format!("{}{}{}", left, token_source(operator), right)
+            }
+            Node::Axis { function, axis, .. } => format!("{}[{}]", function, axis),
+        };
+        write!(f, "{}", source)
+    }
+}
+
+fn token_source(token: &Token) -> String {
+    match token {
+        Token::Function(c) | Token::MonadicOperator(c) | Token::DyadicOperator(c) => c.to_string(),
+        Token::Number(n) => format_number(*n),
+        Token::String(string) => format!("'{}'", string.replace('\'', "''")),
+        Token::Id(name) | Token::FunctionId(name) => name.clone(),
+        Token::Zilde => "⍬".to_string(),
+        _ => format!("{:?}", token),
+    }
+}
+
+fn statements(nodes: &[Node]) -> String {
+    nodes.iter().map(Node::to_string).collect::<Vec<String>>().join(" ⋄ ")
+}
+
+// Write `right` after `left`, with a space between them when they would
+// otherwise run together into a single name or number.
+fn adjoin(left: &str, right: &str) -> String {
+    let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || "_¯.⍺⍵∇⎕∆⍙".contains(c));
+    match word(left.chars().last()) && word(right.chars().next()) {
+        true => format!("{} {}", left, right),
+        false => format!("{}{}", left, right),
+    }
+}
 
 pub mod visit {
     use crate::node::Node;
-    use crate::token::Token;
+    use crate::token::{Span, Token};
 
     pub trait Visitor<T> {
         fn visit_scalar(&mut self, token: &Token) -> T;
-        fn visit_array(&mut self, values: &Vec<Node>) -> T;
+        fn visit_array(&mut self, values: &[Node]) -> T;
         fn visit_monad(&mut self, operator: &Node, right: &Node) -> T;
         fn visit_dyad(&mut self, alpha: &Node, operator: &Node, omega: &Node) -> T;
-        fn visit_f(&mut self, token: &Token, valence: i32) -> T;
-        fn visit_stmt(&mut self, children: &Vec<Node>) -> T;
+        fn visit_f(&mut self, token: &Token, valence: i32, span: Span) -> T;
+        fn visit_stmt(&mut self, children: &[Node]) -> T;
+        fn visit_assignment(&mut self, varname: &Node, value: &Node) -> T;
+        fn visit_var(&mut self, token: &Token) -> T;
+        fn visit_dfn(&mut self, body: &[Node], span: Span) -> T;
+        fn visit_guard(&mut self, condition: &Node, result: &Node) -> T;
+        fn visit_operator(&mut self, operator: &Token, child: &Node, span: Span) -> T;
+        fn visit_dyadic_operator(&mut self, operator: &Token, left: &Node, right: &Node, span: Span) -> T;
+        fn visit_axis(&mut self, function: &Node, axis: &Node, span: Span) -> T;
     }
 
     pub trait Acceptor<T> {
@@ -60,13 +192,19 @@ pub mod visit {
 impl<T> visit::Acceptor<T> for Node{
     fn accept(&self, visitor: &mut dyn visit::Visitor<T>, valence: Option<i32>) -> T {
         match self {
-            Node::Scalar{token} => visitor.visit_scalar(token),
-            Node::Array{values} => visitor.visit_array(values),
-            Node::Monad{operator, right} => visitor.visit_monad(operator, right.as_ref().unwrap()),
-            Node::Dyad{left, operator, right} => visitor.visit_dyad(left.as_ref().unwrap(), operator, right.as_ref().unwrap()),
-            Node::F{token} => visitor.visit_f(token, valence.unwrap_or(1)),
-            Node::Statement{children} => visitor.visit_stmt(children),
-            _ => todo!()
+            Node::Scalar{token, ..} => visitor.visit_scalar(token),
+            Node::Array{values, ..} => visitor.visit_array(values),
+            Node::Monad{operator, right, ..} => visitor.visit_monad(operator, right.as_ref().unwrap()),
+            Node::Dyad{left, operator, right, ..} => visitor.visit_dyad(left.as_ref().unwrap(), operator, right.as_ref().unwrap()),
+            Node::F{token, span} => visitor.visit_f(token, valence.unwrap_or(1), *span),
+            Node::Statement{children, ..} => visitor.visit_stmt(children),
+            Node::Assignement{varname, value, ..} => visitor.visit_assignment(varname, value),
+            Node::Var{token, ..} => visitor.visit_var(token),
+            Node::Dfn{body, span} => visitor.visit_dfn(body, *span),
+            Node::MonadicOperator{operator, child, span} => visitor.visit_operator(operator, child.as_ref().unwrap(), *span),
+            Node::DyadicOperator{operator, left, right, span} => visitor.visit_dyadic_operator(operator, left, right, *span),
+            Node::Guard{condition, result, ..} => visitor.visit_guard(condition, result),
+            Node::Axis{function, axis, span} => visitor.visit_axis(function, axis, *span),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,176 @@
+use crate::complex::Complex;
+
+/// The relative difference under which two floats are considered equal.
+pub const COMPARISON_TOLERANCE: f64 = 1e-14;
+
+/// The largest integer below which every integer is exactly a float.
+const EXACT_FLOAT_LIMIT: f64 = 9007199254740992.;
+
+/// An APL number, stored in the narrowest of a numeric tower of booleans,
+/// integers, floats and complex numbers that represents it exactly.
+///
+/// Numbers are always built through `Number::int`, `Number::float` and
+/// `Number::complex`, which
+/// pick the narrowest representation, so a number has a single representation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Complex(Complex),
+}
+
+impl Number {
+    pub fn int(n: i64) -> Number {
+        match n {
+            0 => Number::Bool(false),
+            1 => Number::Bool(true),
+            _ => Number::Int(n),
+        }
+    }
+
+    /// A float, demoted to an integer when it is whole and exact.
+    pub fn float(x: f64) -> Number {
+        if x.fract() == 0. && x.abs() < EXACT_FLOAT_LIMIT {
+            Number::int(x as i64)
+        } else {
+            Number::Float(x)
+        }
+    }
+
+    /// A complex number, demoted to a real one when its imaginary part is 0.
+    pub fn complex(z: Complex) -> Number {
+        match z.im == 0. {
+            true => Number::float(z.re),
+            false => Number::Complex(z),
+        }
+    }
+
+    /// The real part of the number as a float.
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Number::Bool(b) => b as i64 as f64,
+            Number::Int(n) => n as f64,
+            Number::Float(x) => x,
+            Number::Complex(z) => z.re,
+        }
+    }
+
+    pub fn as_complex(self) -> Complex {
+        match self {
+            Number::Complex(z) => z,
+            _ => Complex::real(self.as_f64()),
+        }
+    }
+
+    pub fn is_complex(self) -> bool {
+        matches!(self, Number::Complex(_))
+    }
+
+    pub fn is_nan(self) -> bool {
+        matches!(self, Number::Float(x) if x.is_nan())
+    }
+
+    /// The number as an integer, if it is stored as one.
+    pub fn as_int(self) -> Option<i64> {
+        match self {
+            Number::Bool(b) => Some(b as i64),
+            Number::Int(n) => Some(n),
+            Number::Float(_) | Number::Complex(_) => None,
+        }
+    }
+
+    pub fn is_finite(self) -> bool {
+        let z = self.as_complex();
+        z.re.is_finite() && z.im.is_finite()
+    }
+
+    /// Apply `exact` to integers, falling back to `inexact` on floats or when
+    /// the integer result overflows. Neither number may be complex.
+    pub fn exact_or(
+        self,
+        other: Number,
+        exact: fn(i64, i64) -> Option<i64>,
+        inexact: fn(f64, f64) -> f64,
+    ) -> Number {
+        match (self.as_int(), other.as_int()) {
+            (Some(a), Some(b)) => match exact(a, b) {
+                Some(n) => Number::int(n),
+                None => Number::float(inexact(a as f64, b as f64)),
+            },
+            _ => Number::float(inexact(self.as_f64(), other.as_f64())),
+        }
+    }
+
+    /// Whether two numbers are equal, within the comparison tolerance when
+    /// either is not an integer.
+    pub fn tolerantly_equals(self, other: Number) -> bool {
+        match (self.as_int(), other.as_int()) {
+            (Some(a), Some(b)) => a == b,
+            _ => {
+                let (a, b) = (self.as_complex(), other.as_complex());
+                a == b || a.sub(b).abs() <= COMPARISON_TOLERANCE * a.abs().max(b.abs())
+            }
+        }
+    }
+
+    /// Whether `self` is smaller than `other` and not tolerantly equal to it.
+    pub fn is_less_than(self, other: Number) -> bool {
+        match (self.as_int(), other.as_int()) {
+            (Some(a), Some(b)) => a < b,
+            _ => self.as_f64() < other.as_f64() && !self.tolerantly_equals(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::functions;
+    use crate::value::Value;
+
+    // Apply the scalar primitive `f` to two numbers.
+    fn apply(f: fn(&Value, &Value) -> Result<Value, Error>, a: Number, b: Number) -> Number {
+        match f(&Value::Number(a), &Value::Number(b)) {
+            Ok(Value::Number(n)) => n,
+            result => panic!("expected a number, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn integers_stay_exact() {
+        let sum = apply(functions::plus, Number::int(16777217), Number::int(0));
+        assert_eq!(sum, Number::Int(16777217));
+        let power = apply(functions::power, Number::int(2), Number::int(62));
+        assert_eq!(power, Number::Int(1 << 62));
+        assert_eq!(Number::int(0), Number::Bool(false));
+        assert_eq!(Number::int(1), Number::Bool(true));
+    }
+
+    #[test]
+    fn overflowing_integers_become_floats() {
+        let sum = apply(functions::plus, Number::int(i64::MAX), Number::int(1));
+        assert_eq!(sum, Number::Float(9223372036854775808.));
+        let power = apply(functions::power, Number::int(2), Number::int(63));
+        assert_eq!(power, Number::Float(9223372036854775808.));
+        let product = apply(functions::times, Number::int(i64::MIN), Number::int(-1));
+        assert_eq!(product, Number::Float(9223372036854775808.));
+    }
+
+    #[test]
+    fn whole_floats_become_integers() {
+        assert_eq!(apply(functions::times, Number::int(2), Number::Float(0.5)), Number::Bool(true));
+        assert_eq!(apply(functions::times, Number::int(3), Number::Float(0.5)), Number::Float(1.5));
+        assert_eq!(Number::float(-4.), Number::Int(-4));
+        assert_eq!(Number::float(EXACT_FLOAT_LIMIT), Number::Float(EXACT_FLOAT_LIMIT));
+        assert_eq!(Number::complex(Complex::new(2., 0.)), Number::Int(2));
+    }
+
+    #[test]
+    fn floats_are_compared_tolerantly() {
+        assert!(Number::Float(0.1 + 0.2).tolerantly_equals(Number::Float(0.3)));
+        assert!(!Number::Float(0.1 + 0.2).is_less_than(Number::Float(0.3)));
+        assert!(!Number::int(i64::MAX).tolerantly_equals(Number::int(i64::MAX - 1)));
+    }
+}
@@ -1,50 +1,200 @@
-use crate::token::Token;
+use crate::error::Error;
+use crate::token::{SpannedToken, Token};
 use crate::node::Node;
+use std::collections::HashSet;
 
-pub fn parse(tokens: &Vec<Token>) -> Result<Node, String> {
-    let statement_list: Node = parse_statement_list(&tokens, 0)?;
+/// Parse a reversed token stream.
+///
+/// `functions` holds the names already bound to functions, so that they can
+/// be told apart from arrays.
+pub fn parse(tokens: &[SpannedToken], functions: &HashSet<String>) -> Result<Node, Error> {
+    let tokens = classify_names(tokens, functions);
+    let (statement_list, index) = parse_statement_list(&tokens, 0)?;
+    if tokens[index].token != Token::Eof {
+        return Err(error(&tokens, index, ""));
+    }
     Ok(statement_list)
 }
 
-fn parse_statement_list(tokens: &Vec<Token>, mut index: usize) -> Result<Node, String> {
+// A syntax error located at the token `index`.
+fn error(tokens: &[SpannedToken], index: usize, message: &str) -> Error {
+    Error::syntax(message).at(tokens[index].span)
+}
+
+// Names are only bound to arrays or functions at run time, but the parser
+// needs to know which is which. A name is a function if it is already known
+// as one, or if a function expression is assigned to it earlier in the source.
+fn classify_names(tokens: &[SpannedToken], functions: &HashSet<String>) -> Vec<SpannedToken> {
+    let mut functions = functions.clone();
+    functions.insert("∇".to_string());
+    // Walk the source in reading order
+    let mut source: Vec<SpannedToken> = tokens.iter().rev().cloned().collect();
+    for i in 0..source.len() {
+        let name = match &source[i].token {
+            Token::Id(name) | Token::FunctionId(name) => name.clone(),
+            Token::Function(c @ ('/' | '⌿' | '\\' | '⍀')) => {
+                if i > 0 && matches!(source[i - 1].token, Token::FunctionId(_)) {
+                    source[i].token = Token::MonadicOperator(*c);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if source.get(i + 1).map(|next| &next.token) == Some(&Token::Assign) && name != "∇" {
+            if is_function_expression(&source[i + 2..], &functions) {
+                functions.insert(name.clone());
+            } else {
+                functions.remove(&name);
+            }
+        }
+        source[i].token = match functions.contains(&name) {
+            true => Token::FunctionId(name),
+            false => Token::Id(name),
+        };
+    }
+    source.reverse();
+    source
+}
+
+// Whether `tokens`, in reading order, start with a function expression that
+// makes up the rest of the statement.
+fn is_function_expression(tokens: &[SpannedToken], functions: &HashSet<String>) -> bool {
+    let mut depth = 0;
+    let mut function = false;
+    let mut i = 0;
+    while let Some(SpannedToken { token, .. }) = tokens.get(i) {
+        i += 1;
+        match token {
+            Token::Brace('{') => {
+                depth += 1;
+                function = true;
+            }
+            Token::Brace('}') if depth > 0 => depth -= 1,
+            // An axis is part of the function it follows
+            Token::Bracket('[') => depth += 1,
+            Token::Bracket(']') if depth > 0 => depth -= 1,
+            _ if depth > 0 => {}
+            // A function in parentheses is still a function
+            Token::Paren('(') => {
+                let end = i + matching_paren(tokens[i..].iter(), Token::Paren('('));
+                if !is_function_expression(&tokens[i..end], functions) {
+                    return false;
+                }
+                function = true;
+                i = end + 1;
+            }
+            Token::Function(_) | Token::MonadicOperator(_) | Token::DyadicOperator(_) => function = true,
+            Token::FunctionId(_) => function = true,
+            Token::Id(name) if functions.contains(name) => function = true,
+            Token::Diamond | Token::Eof | Token::Guard | Token::Paren(')') | Token::Brace('}') => {
+                return function;
+            }
+            _ => return false,
+        }
+    }
+    function
+}
+
+// The number of tokens before the parenthesis closing the one `opening` was,
+// or all of them if it is not closed.
+fn matching_paren<'a>(tokens: impl Iterator<Item = &'a SpannedToken>, opening: Token) -> usize {
+    let mut depth = 0;
+    let mut count = 0;
+    for SpannedToken { token, .. } in tokens {
+        match token {
+            Token::Paren(_) if *token == opening => depth += 1,
+            Token::Paren(_) if depth == 0 => return count,
+            Token::Paren(_) => depth -= 1,
+            _ => {}
+        }
+        count += 1;
+    }
+    count
+}
+
+// Whether the reversed tokens from `index` start with a function, possibly
+// in parentheses.
+fn is_function_start(tokens: &[SpannedToken], index: usize) -> bool {
+    match tokens[index].token {
+        Token::Function(_)
+        | Token::MonadicOperator(_)
+        | Token::FunctionId(_)
+        | Token::Brace('}')
+        | Token::Bracket(']') => true,
+        Token::Paren(')') => is_parenthesised_function(tokens, index),
+        _ => false,
+    }
+}
+
+// Whether the reversed tokens from `index` start with a function in
+// parentheses, such as `(+/)`.
+fn is_parenthesised_function(tokens: &[SpannedToken], index: usize) -> bool {
+    let inner = &tokens[index + 1..];
+    let end = matching_paren(inner.iter(), Token::Paren(')'));
+    let source: Vec<SpannedToken> = inner[..end].iter().rev().cloned().collect();
+    is_function_expression(&source, &HashSet::new())
+}
+
+fn parse_statement_list(tokens: &[SpannedToken], mut index: usize) -> Result<(Node, usize), Error> {
     let mut statement_list: Vec<Node> = Vec::new();
     let mut statement: Node;
-    (statement, index) = parse_statement(tokens, index)?;
-    statement_list.push(statement);
-    while tokens[index] == Token::Diamond {
+    loop {
+        // Skip empty statements such as blank lines
+        if !matches!(tokens[index].token, Token::Diamond | Token::Eof) {
+            (statement, index) = parse_statement(tokens, index)?;
+            statement_list.push(statement);
+        }
+        if tokens[index].token != Token::Diamond {
+            break;
+        }
         index += 1;
-        (statement, index) = parse_statement(tokens, index)?;
-        statement_list.push(statement);
     }
+    // Tokens are read from right to left, so the last statement comes first.
+    statement_list.reverse();
+    let span = match (statement_list.first(), statement_list.last()) {
+        (Some(first), Some(last)) => first.span().to(last.span()),
+        _ => tokens[index].span,
+    };
     let root = Node::Statement {
         children: statement_list,
+        span,
     };
-    Ok(root)
+    Ok((root, index))
 }
 
-fn parse_statement(tokens: &Vec<Token>, mut index: usize) -> Result<(Node, usize), String> {
+fn parse_statement(tokens: &[SpannedToken], mut index: usize) -> Result<(Node, usize), Error> {
     // println!("Parsing statement from {:?}", &tokens[index..]);
     let mut statement: Node;
+    if is_function_start(tokens, index) {
+        // A function on its own, which can only be named
+        (statement, index) = parse_function(tokens, index)?;
+        if tokens[index].token == Token::Assign {
+            (statement, index) = parse_assignment(tokens, index, statement)?;
+        }
+        return Ok((statement, index));
+    }
     (statement, index) = parse_array(tokens, index)?;
     loop {
-        match tokens[index] {
+        match tokens[index].token {
             Token::Assign => {
-                index += 1;
-                statement = Node::Assignement {
-                    varname: Box::new(Node::Var {
-                        token: tokens[index].clone(),
-                    }),
-                    value: Box::new(statement),
-                }
+                (statement, index) = parse_assignment(tokens, index, statement)?;
             }
-            Token::Function(_) | Token::MonadicOperator(_) => {
+            _ if is_function_start(tokens, index) => {
                 let func: Node;
                 (func, index) = parse_function(tokens, index)?;
-                match tokens[index] {
-                    Token::Paren(')') | Token::Number(_) | Token::Id(_) => {
+                match tokens[index].token {
+                    Token::Paren(')') if is_parenthesised_function(tokens, index) => {
+                        statement = Node::Monad {
+                            span: func.span().to(statement.span()),
+                            operator: Box::new(func),
+                            right: Some(Box::new(statement)),
+                        }
+                    }
+                    Token::Paren(')') | Token::Number(_) | Token::String(_) | Token::Zilde | Token::Id(_) => {
                         let array;
                         (array, index) = parse_array(tokens, index)?;
                         statement = Node::Dyad {
+                            span: array.span().to(statement.span()),
                             left: Some(Box::new(array)),
                             operator: Box::new(func),
                             right: Some(Box::new(statement)),
@@ -52,6 +202,7 @@ fn parse_statement(tokens: &Vec<Token>, mut index: usize) -> Result<(Node, usize
                     }
                     _ => {
                         statement = Node::Monad {
+                            span: func.span().to(statement.span()),
                             operator: Box::new(func),
                             right: Some(Box::new(statement)),
                         }
@@ -63,24 +214,57 @@ fn parse_statement(tokens: &Vec<Token>, mut index: usize) -> Result<(Node, usize
             }
         }
     }
-    return Ok((statement, index));
+    Ok((statement, index))
+}
+
+fn parse_assignment(tokens: &[SpannedToken], mut index: usize, value: Node) -> Result<(Node, usize), Error> {
+    index = eat(tokens, index, Token::Assign)?;
+    if !matches!(tokens[index].token, Token::Id(_) | Token::FunctionId(_)) {
+        return Err(error(tokens, index, "can only assign to a name"));
+    }
+    let node = Node::Assignement {
+        varname: Box::new(Node::Var {
+            token: tokens[index].token.clone(),
+            span: tokens[index].span,
+        }),
+        span: tokens[index].span.to(value.span()),
+        value: Box::new(value),
+    };
+    index += 1;
+    Ok((node, index))
 }
 
-pub fn parse_array(tokens: &Vec<Token>, mut index: usize) -> Result<(Node, usize), String> {
+pub fn parse_array(tokens: &[SpannedToken], mut index: usize) -> Result<(Node, usize), Error> {
     // println!("Parsing array from {:?}", &tokens[index..]);
-    let mut node: Vec<Node> = [].to_vec();
+    let mut node: Vec<Node> = Vec::new();
     loop {
-        match tokens[index] {
-            Token::Paren(')') => {
+        let SpannedToken { token, span } = &tokens[index];
+        match token {
+            Token::Paren(')') if !is_parenthesised_function(tokens, index) => {
                 index += 1;
                 let statement: Node;
                 (statement, index) = parse_statement(tokens, index)?;
                 node.push(statement);
                 index = eat(tokens, index, Token::Paren('('))?;
             }
-            Token::Number(_) => {
+            Token::Number(_) | Token::String(_) => {
                 node.push(Node::Scalar {
-                    token: tokens[index].clone(),
+                    token: token.clone(),
+                    span: *span,
+                });
+                index += 1;
+            }
+            Token::Zilde => {
+                node.push(Node::Array {
+                    values: Vec::new(),
+                    span: *span,
+                });
+                index += 1;
+            }
+            Token::Id(_) => {
+                node.push(Node::Var {
+                    token: token.clone(),
+                    span: *span,
                 });
                 index += 1;
             }
@@ -91,22 +275,39 @@ pub fn parse_array(tokens: &Vec<Token>, mut index: usize) -> Result<(Node, usize
     }
     node.reverse();
     match node.len() {
-        0 => {
-            return Err("Failed to parse scalars inside an array.".to_string());
-        }
-        1 => {
-            return Ok((node[0].clone(), index));
-        }
-        _ => {
-            return Ok((Node::Array { values: node }, index));
-        }
+        0 => Err(error(tokens, index, "expected an array")),
+        1 => Ok((node.remove(0), index)),
+        _ => Ok((
+            Node::Array {
+                span: node[0].span().to(node[node.len() - 1].span()),
+                values: node,
+            },
+            index,
+        )),
     }
 }
 
-fn parse_function(tokens: &Vec<Token>, mut index: usize) -> Result<(Node, usize), String> {
+fn parse_function(tokens: &[SpannedToken], mut index: usize) -> Result<(Node, usize), Error> {
     // println!("Parsing function from {:?}", &tokens[index..]);
     let mut node: Node;
-    match tokens[index] {
+    match tokens[index].token {
+        Token::Paren(')') => {
+            (node, index) = parse_function(tokens, index + 1)?;
+            index = eat(tokens, index, Token::Paren('('))?;
+        }
+        Token::Bracket(']') => {
+            let end = tokens[index].span;
+            let axis;
+            (axis, index) = parse_statement(tokens, index + 1)?;
+            index = eat(tokens, index, Token::Bracket('['))?;
+            let function;
+            (function, index) = parse_function(tokens, index)?;
+            node = Node::Axis {
+                span: function.span().to(end),
+                function: Box::new(function),
+                axis: Box::new(axis),
+            };
+        }
         Token::MonadicOperator(_) => {
             (node, index) = parse_mop(tokens, index)?;
             let function;
@@ -114,40 +315,98 @@ fn parse_function(tokens: &Vec<Token>, mut index: usize) -> Result<(Node, usize)
             if let Node::MonadicOperator {
                 operator: _,
                 ref mut child,
+                ref mut span,
             } = node
             {
+                *span = span.to(function.span());
                 *child = Some(Box::new(function));
             }
         }
         _ => {
             (node, index) = parse_f(tokens, index)?;
+            if let Token::DyadicOperator(_) = tokens[index].token {
+                let operator = tokens[index].token.clone();
+                let left;
+                (left, index) = parse_f(tokens, index + 1)?;
+                node = Node::DyadicOperator {
+                    span: left.span().to(node.span()),
+                    operator,
+                    left: Box::new(left),
+                    right: Box::new(node),
+                };
+            }
         }
     }
-    return Ok((node, index));
+    Ok((node, index))
 }
 
-fn parse_mop(tokens: &Vec<Token>, mut index: usize) -> Result<(Node, usize), String> {
+fn parse_mop(tokens: &[SpannedToken], mut index: usize) -> Result<(Node, usize), Error> {
     // println!("Parsing mop from {:?}", &tokens[index..]);
     let mop: Node = Node::MonadicOperator {
-        operator: tokens[index].clone(),
+        operator: tokens[index].token.clone(),
         child: None,
+        span: tokens[index].span,
     };
     index += 1;
     Ok((mop, index))
 }
 
-fn parse_f(tokens: &Vec<Token>, mut index: usize) -> Result<(Node, usize), String> {
+fn parse_f(tokens: &[SpannedToken], mut index: usize) -> Result<(Node, usize), Error> {
     // println!("Parsing f from {:?}", &tokens[index..]);
-    let node: Node = Node::F {
-        token: tokens[index].clone(),
+    let SpannedToken { token, span } = &tokens[index];
+    let node: Node = match token {
+        Token::Function(_) => Node::F {
+            token: token.clone(),
+            span: *span,
+        },
+        Token::FunctionId(_) => Node::Var {
+            token: token.clone(),
+            span: *span,
+        },
+        Token::Brace('}') => return parse_dfn(tokens, index),
+        _ => return Err(error(tokens, index, "expected a function")),
     };
     index += 1;
     Ok((node, index))
 }
 
-fn eat(tokens: &Vec<Token>, index: usize, token: Token) -> Result<usize, String> {
-    if tokens[index] == token {
-        return Ok(index);
+fn parse_dfn(tokens: &[SpannedToken], mut index: usize) -> Result<(Node, usize), Error> {
+    let end = tokens[index].span;
+    index = eat(tokens, index, Token::Brace('}'))?;
+    let mut body: Vec<Node> = Vec::new();
+    let mut statement: Node;
+    loop {
+        if !matches!(tokens[index].token, Token::Diamond | Token::Brace('{')) {
+            (statement, index) = parse_statement(tokens, index)?;
+            if tokens[index].token == Token::Guard {
+                let condition: Node;
+                (condition, index) = parse_statement(tokens, index + 1)?;
+                statement = Node::Guard {
+                    span: condition.span().to(statement.span()),
+                    condition: Box::new(condition),
+                    result: Box::new(statement),
+                };
+            }
+            body.push(statement);
+        }
+        if tokens[index].token != Token::Diamond {
+            break;
+        }
+        index += 1;
+    }
+    let span = tokens[index].span.to(end);
+    index = eat(tokens, index, Token::Brace('{'))?;
+    body.reverse();
+    Ok((Node::Dfn { body, span }, index))
+}
+
+fn eat(tokens: &[SpannedToken], index: usize, token: Token) -> Result<usize, Error> {
+    if tokens[index].token == token {
+        return Ok(index + 1);
     }
-    return Err("Bad token".to_string());
+    let expected = match token {
+        Token::Paren(c) | Token::Brace(c) | Token::Bracket(c) => c.to_string(),
+        _ => format!("{:?}", token),
+    };
+    Err(error(tokens, index, &format!("expected {}", expected)))
 }
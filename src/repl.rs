@@ -0,0 +1,322 @@
+use crate::error::Error;
+use crate::interpreter::{Interpreter, STACK_SIZE};
+use crate::lexer;
+use crate::node::Node;
+use crate::parser;
+use crate::value::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+const PROMPT: &str = "      ";
+
+/// Lex, parse and evaluate the statements of `source` against `interpreter`,
+/// passing the value of each statement that is not an assignment to
+/// `display` as soon as it is computed.
+pub fn execute<F>(interpreter: &mut Interpreter, source: &str, mut display: F) -> Result<(), Error>
+where
+    F: FnMut(Value),
+{
+    let mut tokens = lexer::scan_tokens(source)?;
+    tokens.reverse();
+    let ast = parser::parse(&tokens, &interpreter.function_names())?;
+    let statements = match &ast {
+        Node::Statement { children, .. } => children.as_slice(),
+        _ => std::slice::from_ref(&ast),
+    };
+    for statement in statements {
+        let value = interpreter.interpret(statement)?;
+        // The result of an assignment is not displayed
+        if !matches!(statement, Node::Assignement { .. }) {
+            display(value);
+        }
+    }
+    Ok(())
+}
+
+/// Evaluate `source` in a new interpreter and format what it displays, one
+/// value per line.
+#[cfg(test)]
+pub fn evaluate(source: &str) -> Result<String, Error> {
+    let mut lines: Vec<String> = Vec::new();
+    execute(&mut Interpreter::new(), source, |value| lines.push(value.to_string()))?;
+    Ok(lines.join("\n"))
+}
+
+/// Read lines from the standard input and evaluate them until `)off` or the
+/// end of the input.
+pub fn run() {
+    let mut interpreter = Interpreter::new();
+    // `main` runs the interpreter on a thread of this size
+    interpreter.set_stack_size(STACK_SIZE);
+    let mut history = History::open();
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    while let Some(mut source) = read_line(&mut input, &history.entries, PROMPT) {
+        // A dfn can span several lines until its braces are balanced
+        while unclosed_braces(&source) > 0 {
+            match read_line(&mut input, &history.entries, PROMPT) {
+                Some(line) => {
+                    source.push('\n');
+                    source.push_str(&line);
+                }
+                None => break,
+            }
+        }
+        if source.trim().is_empty() {
+            continue;
+        }
+        history.add(&source);
+        match source.trim() {
+            ")off" => break,
+            ")history" => {
+                for entry in &history.entries {
+                    println!("{}", entry);
+                }
+            }
+            _ => {
+                let display = |value: Value| println!("{}", value);
+                if let Err(error) = execute(&mut interpreter, &source, display) {
+                    println!("{}", error.report(&source));
+                }
+            }
+        }
+    }
+}
+
+// Read a line of `input` after showing `prompt`. On a terminal, the up and
+// down arrows recall the entries of `history`.
+fn read_line<B: BufRead>(input: &mut B, history: &[String], prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().ok()?;
+    if io::stdin().is_terminal() {
+        if let Some(settings) = raw_mode() {
+            let line = edit_line(input, &mut io::stdout(), history, prompt);
+            restore_mode(&settings);
+            return line;
+        }
+    }
+    let mut line = String::new();
+    match input.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => {
+            let end = line.strip_suffix('\n').unwrap_or(&line);
+            Some(end.strip_suffix('\r').unwrap_or(end).to_string())
+        }
+    }
+}
+
+// Let the terminal pass keys as they are typed, without echoing them or
+// turning Ctrl-C into a signal, and return its previous settings. `None`
+// when `stty` cannot change them.
+fn raw_mode() -> Option<String> {
+    let settings = Command::new("stty").arg("-g").stdin(Stdio::inherit()).output().ok()?;
+    let changed = Command::new("stty").args(["-icanon", "-echo", "-isig"]).status().ok()?;
+    (settings.status.success() && changed.success())
+        .then(|| String::from_utf8_lossy(&settings.stdout).trim().to_string())
+}
+
+fn restore_mode(settings: &str) {
+    let _ = Command::new("stty").arg(settings).status();
+}
+
+// Edit a line from the keys read from `input`, showing it on `output`, with
+// the up and down arrows stepping through `history`. Ctrl-C abandons the
+// line and Ctrl-D on an empty line ends the input.
+fn edit_line<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    history: &[String],
+    prompt: &str,
+) -> Option<String> {
+    let mut line: Vec<u8> = Vec::new();
+    // The entry being shown, `history.len()` standing for the typed line
+    let mut position = history.len();
+    let mut typed: Vec<u8> = Vec::new();
+    let mut bytes = input.by_ref().bytes();
+    loop {
+        let byte = bytes.next()?.ok()?;
+        let shown = line.clone();
+        match byte {
+            b'\n' | b'\r' => {
+                writeln!(output).ok()?;
+                return Some(String::from_utf8_lossy(&line).into_owned());
+            }
+            3 => {
+                writeln!(output).ok()?;
+                return Some(String::new());
+            }
+            4 if line.is_empty() => {
+                writeln!(output).ok()?;
+                return None;
+            }
+            // Remove the last character, with all of its bytes
+            8 | 127 => {
+                while let Some(byte) = line.pop() {
+                    if byte & 0xC0 != 0x80 {
+                        break;
+                    }
+                }
+            }
+            0x1B => {
+                let (Some(Ok(b'[')), Some(Ok(key))) = (bytes.next(), bytes.next()) else {
+                    continue;
+                };
+                let next = match key {
+                    b'A' => position.checked_sub(1),
+                    b'B' if position < history.len() => Some(position + 1),
+                    _ => None,
+                };
+                if let Some(next) = next {
+                    if position == history.len() {
+                        typed = line.clone();
+                    }
+                    position = next;
+                    line = history.get(position).map_or(typed.clone(), |entry| entry.as_bytes().to_vec());
+                }
+            }
+            _ if byte < 0x20 && byte != b'\t' => {}
+            _ => line.push(byte),
+        }
+        redraw(output, prompt, &shown, &line).ok()?;
+    }
+}
+
+// Replace `shown`, displayed after `prompt`, with `line`.
+fn redraw<W: Write>(output: &mut W, prompt: &str, shown: &[u8], line: &[u8]) -> io::Result<()> {
+    if shown == line {
+        return Ok(());
+    }
+    // Go back to the first row of a multi-line entry before clearing it
+    let rows = shown.iter().filter(|&&byte| byte == b'\n').count();
+    if rows > 0 {
+        write!(output, "\x1B[{}A", rows)?;
+    }
+    write!(output, "\r\x1B[J{}", prompt)?;
+    output.write_all(line)?;
+    output.flush()
+}
+
+/// How many `{` are still open in `source`, ignoring the ones in strings and
+/// comments.
+pub fn unclosed_braces(source: &str) -> i32 {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut in_comment = false;
+    for c in source.chars() {
+        match c {
+            '\n' => in_comment = false,
+            _ if in_comment => {}
+            '\'' => in_string = !in_string,
+            '⍝' if !in_string => in_comment = true,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => depth -= 1,
+            _ => {}
+        }
+    }
+    depth
+}
+
+/// The inputs of the current and previous sessions, kept in
+/// `~/.azote_history`.
+struct History {
+    entries: Vec<String>,
+    file: Option<File>,
+}
+
+impl History {
+    fn open() -> History {
+        let path = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".azote_history"));
+        let mut entries: Vec<String> = Vec::new();
+        let mut file = None;
+        if let Some(path) = path {
+            if let Ok(existing) = File::open(&path) {
+                entries = BufReader::new(existing)
+                    .lines()
+                    .map_while(Result::ok)
+                    .map(|line| unescape(&line))
+                    .collect();
+            }
+            file = OpenOptions::new().create(true).append(true).open(&path).ok();
+        }
+        History { entries, file }
+    }
+
+    fn add(&mut self, entry: &str) {
+        if let Some(file) = self.file.as_mut() {
+            // History is a convenience, failing to save it is not an error
+            let _ = writeln!(file, "{}", escape(entry));
+        }
+        self.entries.push(entry.to_string());
+    }
+}
+
+// Multi-line entries are stored on a single line of the history file, with
+// their newlines written as `\n` and their backslashes as `\\`.
+fn escape(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut entry = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                entry.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                entry.push('\\');
+                chars.next();
+            }
+            _ => entry.push(c),
+        }
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_entries_survive_escaping() {
+        for entry in ["+\\nums", "f←{\n⍵\n}", "'\\\\n'", "a\\"] {
+            assert_eq!(unescape(&escape(entry)), entry);
+            assert!(!escape(entry).contains('\n'));
+        }
+    }
+
+    #[test]
+    fn statements_other_than_assignments_are_displayed() {
+        assert_eq!(evaluate("a←2 ⋄ a+1 ⋄ ⍳a").unwrap(), "3\n1 2");
+        assert_eq!(evaluate("f←{⍵} ⋄ a←1").unwrap(), "");
+        let mut values: Vec<String> = Vec::new();
+        let display = |value: Value| values.push(value.to_string());
+        assert!(execute(&mut Interpreter::new(), "1 ⋄ 2 ⋄ 1+'a' ⋄ 3", display).is_err());
+        assert_eq!(values, ["1", "2"]);
+    }
+
+    #[test]
+    fn arrows_recall_history_entries() {
+        let history = ["1+1".to_string(), "⍳3".to_string()];
+        let edit = |keys: &str| edit_line(&mut keys.as_bytes(), &mut Vec::new(), &history, PROMPT);
+        assert_eq!(edit("\x1B[A\n").as_deref(), Some("⍳3"));
+        assert_eq!(edit("\x1B[A\x1B[A\x1B[A\n").as_deref(), Some("1+1"));
+        assert_eq!(edit("2×\x1B[A\x1B[B\n").as_deref(), Some("2×"));
+        assert_eq!(edit("\x1B[A\x7F4\n").as_deref(), Some("⍳4"));
+        assert_eq!(edit("⍳\x7F\x7F-1\n").as_deref(), Some("-1"));
+        assert_eq!(edit("1\x03").as_deref(), Some(""));
+        assert_eq!(edit("\x04"), None);
+    }
+
+    #[test]
+    fn braces_in_strings_and_comments_are_ignored() {
+        assert_eq!(unclosed_braces("g←{ ⍝ don't"), 1);
+        assert_eq!(unclosed_braces("g←{ ⍝ don't\n⍵}"), 0);
+        assert_eq!(unclosed_braces("'{'"), 0);
+        assert_eq!(unclosed_braces("'⍝' {"), 1);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::interpreter::{Interpreter, STACK_SIZE};
+use crate::repl::{execute, unclosed_braces};
+use std::fs;
+
+/// Run the APL script at `path`, one statement at a time, with `arguments`
+/// available through `⎕ARG`. Returns the exit status of the process.
+pub fn run(path: &str, arguments: Vec<String>) -> i32 {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            return 1;
+        }
+    };
+    let mut interpreter = Interpreter::new();
+    // `main` runs the interpreter on a thread of this size
+    interpreter.set_stack_size(STACK_SIZE);
+    interpreter.set_arguments(arguments);
+    let mut lines = source.lines().enumerate();
+    while let Some((number, line)) = lines.next() {
+        // Allow the script to be made executable with a shebang
+        if number == 0 && line.starts_with("#!") {
+            continue;
+        }
+        let mut statement = line.to_string();
+        while unclosed_braces(&statement) > 0 {
+            match lines.next() {
+                Some((_, line)) => {
+                    statement.push('\n');
+                    statement.push_str(line);
+                }
+                None => break,
+            }
+        }
+        if let Err(error) = execute(&mut interpreter, &statement, |value| println!("{}", value)) {
+            match error.location(&statement) {
+                Some((line, column)) => {
+                    eprintln!("{}:{}:{}: {}", path, number + line, column, error.report(&statement))
+                }
+                None => eprintln!("{}:{}: {}", path, number + 1, error),
+            }
+            return 1;
+        }
+    }
+    0
+}
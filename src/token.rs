@@ -1,12 +1,45 @@
+use crate::number::Number;
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum Token {
     Paren(char),
+    Brace(char),
+    Bracket(char),
     Function(char),
     MonadicOperator(char),
+    DyadicOperator(char),
     Assign,
-    Number(f32),
-    // String(String),
+    Number(Number),
+    Zilde,
+    String(String),
     Id(String),
+    /// A name the parser knows to be bound to a function.
+    FunctionId(String),
+    Guard,
     Diamond,
     Eof,
-}
\ No newline at end of file
+}
+
+/// A range of bytes in the source.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// A token with the span of source it was read from.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
@@ -1,10 +1,17 @@
 use std::fmt;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
+use crate::error::Error;
+use crate::format;
+use crate::interpreter::Scope;
+use crate::number::Number;
 use crate::node::Node;
 
+pub type MonadicFn = Rc<dyn Fn(&Value) -> Result<Value, Error>>;
+pub type DyadicFn = Rc<dyn Fn(&Value, &Value) -> Result<Value, Error>>;
 
+#[derive(Clone)]
 pub struct MonadicFunctionHolder {
-    pub function: Rc<dyn Fn(&Value) -> Result<Value, String>>,
+    pub function: MonadicFn,
     pub node: Node,
 }
 
@@ -14,8 +21,9 @@ impl fmt::Debug for MonadicFunctionHolder {
     }
 }
 
+#[derive(Clone)]
 pub struct DyadicFunctionHolder {
-    pub function: Rc<dyn Fn(&Value, &Value) -> Result<Value, String>>,
+    pub function: DyadicFn,
     pub node: Node,
 }
 
@@ -25,12 +33,172 @@ impl fmt::Debug for DyadicFunctionHolder {
     }
 }
 
-#[derive(Debug)]
+/// A direct function, `{...}`, whose node is a `Node::Dfn`.
+#[derive(Clone)]
+pub struct DfnHolder {
+    pub node: Node,
+    /// The scope the dfn was defined in. It is held weakly so that a dfn
+    /// stored in that scope does not keep it alive.
+    pub scope: Weak<Scope>,
+}
+
+impl fmt::Debug for DfnHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.node)
+    }
+}
+
+/// A function derived from an operator, such as `+/`, kept as its node so
+/// it can be named and applied later.
+#[derive(Clone)]
+pub struct DerivedFunctionHolder {
+    pub node: Node,
+}
+
+impl fmt::Debug for DerivedFunctionHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.node)
+    }
+}
+
+/// An APL value.
+///
+/// Arrays are stored as a shape vector and their items in ravel order, so a
+/// 3×4 matrix is `Array { shape: vec![3, 4], values }` with 12 values. An
+/// item that is itself an `Array` is an enclosed (nested) item.
+#[derive(Debug, Clone)]
 pub enum Value {
-    Number(f32),
-    // String(String),
-    Array(Vec<Value>),
+    Number(Number),
+    Char(char),
+    Array {
+        shape: Vec<usize>,
+        values: Vec<Value>,
+        /// The fill item of an empty array, which has no first item to take
+        /// it from. `None` stands for a zero.
+        prototype: Option<Box<Value>>,
+    },
     MonadicFunction(MonadicFunctionHolder),
     DyadicFunction(DyadicFunctionHolder),
+    Dfn(DfnHolder),
+    DerivedFunction(DerivedFunctionHolder),
     None,
-}
\ No newline at end of file
+}
+
+impl Value {
+    /// Build an array from a shape and its items in ravel order.
+    ///
+    /// A rank 0 array holding a simple scalar is the scalar itself.
+    pub fn new_array(shape: Vec<usize>, mut values: Vec<Value>) -> Value {
+        debug_assert_eq!(shape.iter().product::<usize>(), values.len());
+        if shape.is_empty() && values[0].is_simple_scalar() {
+            return values.remove(0);
+        }
+        Value::Array { shape, values, prototype: None }
+    }
+
+    pub fn vector(values: Vec<Value>) -> Value {
+        Value::Array {
+            shape: vec![values.len()],
+            values,
+            prototype: None,
+        }
+    }
+
+    /// The value with `fill` as its fill item if it is an empty array, which
+    /// keeps `3⍴0⍴'a'` a vector of blanks.
+    pub fn with_fill(mut self, fill: Value) -> Value {
+        if let Value::Array { values, prototype, .. } = &mut self {
+            if values.is_empty() {
+                *prototype = Some(Box::new(fill));
+            }
+        }
+        self
+    }
+
+    /// The value used to fill arrays built from items like this one: zeros
+    /// and blanks in place of its numbers and characters.
+    pub fn prototype(&self) -> Value {
+        match self {
+            Value::Char(_) => Value::Char(' '),
+            Value::Array { shape, values, prototype } => {
+                let values = values.iter().map(Value::prototype).collect();
+                Value::Array { shape: shape.clone(), values, prototype: prototype.clone() }
+            }
+            _ => Value::Number(Number::int(0)),
+        }
+    }
+
+    /// The fill item of arrays built from the items of this value: the
+    /// prototype of its first item, or of the value itself if it is a scalar.
+    pub fn fill(&self) -> Value {
+        match self {
+            Value::Array { values, prototype, .. } => match (values.first(), prototype) {
+                (Some(first), _) => first.prototype(),
+                (None, Some(prototype)) => (**prototype).clone(),
+                (None, None) => Value::Number(Number::int(0)),
+            },
+            _ => self.prototype(),
+        }
+    }
+
+    pub fn shape(&self) -> Vec<usize> {
+        match self {
+            Value::Array { shape, .. } => shape.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn rank(&self) -> usize {
+        match self {
+            Value::Array { shape, .. } => shape.len(),
+            _ => 0,
+        }
+    }
+
+    /// The items of the value in ravel order. A scalar has a single item.
+    pub fn ravel(&self) -> Vec<Value> {
+        match self {
+            Value::Array { values, .. } => values.clone(),
+            _ => vec![self.clone()],
+        }
+    }
+
+    /// Whether two values are identical in shape and content, numbers being
+    /// compared within the comparison tolerance.
+    pub fn matches(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.tolerantly_equals(*b),
+            (Value::Char(a), Value::Char(b)) => a == b,
+            (
+                Value::Array { shape: shape1, values: values1, .. },
+                Value::Array { shape: shape2, values: values2, .. },
+            ) => {
+                // Empty arrays only differ by their fill items
+                shape1 == shape2
+                    && (!values1.is_empty() || self.fill().matches(&other.fill()))
+                    && values1.iter().zip(values2).all(|(a, b)| a.matches(b))
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_simple_scalar(&self) -> bool {
+        !matches!(self, Value::Array { .. })
+    }
+
+    pub fn is_function(&self) -> bool {
+        matches!(
+            self,
+            Value::MonadicFunction(_)
+                | Value::DyadicFunction(_)
+                | Value::Dfn(_)
+                | Value::DerivedFunction(_)
+        )
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format::format(self))
+    }
+}